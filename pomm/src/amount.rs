@@ -0,0 +1,254 @@
+use crate::config::Ticker;
+use anyhow::anyhow;
+use phoenix::program::MarketHeader;
+use phoenix::quantities::WrapperU64;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A config amount, either raw (atoms or lots, depending on the key) or in UI units of one
+/// of the market tokens such as "100 USDC" or "0.5 SOL".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Amount {
+    Raw(u64),
+    Ui { amount: String, symbol: String },
+}
+
+impl Default for Amount {
+    fn default() -> Self {
+        Amount::Raw(0)
+    }
+}
+
+impl Amount {
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Amount::Raw(raw) => *raw == 0,
+            Amount::Ui { amount, .. } => amount.chars().all(|c| c == '0' || c == '.'),
+        }
+    }
+
+    /// Returns the amount in quote atoms, UI amounts must be in the quote token.
+    pub fn to_quote_atoms(&self, header: &MarketHeader, ticker: &Ticker) -> anyhow::Result<u64> {
+        self.to_atoms(&ticker.quote, header.quote_params.decimals)
+    }
+
+    /// Returns the amount in base lots, UI amounts must be in the base token and round down
+    /// to whole lots.
+    pub fn to_base_lots(&self, header: &MarketHeader, ticker: &Ticker) -> anyhow::Result<u64> {
+        match self {
+            Amount::Raw(base_lots) => Ok(*base_lots),
+            Amount::Ui { .. } => Ok(self.to_atoms(&ticker.base, header.base_params.decimals)?
+                / header.get_base_lot_size().as_u64()),
+        }
+    }
+
+    fn to_atoms(&self, expected_symbol: &str, decimals: u32) -> anyhow::Result<u64> {
+        match self {
+            Amount::Raw(atoms) => Ok(*atoms),
+            Amount::Ui { amount, symbol } => {
+                if !symbol.eq_ignore_ascii_case(expected_symbol) {
+                    return Err(anyhow!(
+                        "Expected an amount in {}, got {}",
+                        expected_symbol,
+                        self
+                    ));
+                }
+                ui_amount_to_atoms(amount, decimals)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Amount::Raw(raw) => write!(f, "{}", raw),
+            Amount::Ui { amount, symbol } => write!(f, "{} {}", amount, symbol),
+        }
+    }
+}
+
+impl FromStr for Amount {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            anyhow!(
+                "Invalid amount({}), expected e.g. 100000000 or \"100 USDC\"",
+                s
+            )
+        };
+        let mut parts = s.split_whitespace();
+        let (amount, symbol) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(amount), None, None) => {
+                return amount.parse().map(Amount::Raw).map_err(|_| invalid())
+            }
+            (Some(amount), Some(symbol), None) => (amount, symbol),
+            _ => return Err(invalid()),
+        };
+        let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+        let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+            return Err(invalid());
+        }
+        Ok(Amount::Ui {
+            amount: amount.to_string(),
+            symbol: symbol.to_string(),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct AmountVisitor;
+
+        impl<'de> Visitor<'de> for AmountVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a non-negative integer or a string like \"100 USDC\"")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Amount, E> {
+                Ok(Amount::Raw(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Amount, E> {
+                u64::try_from(value)
+                    .map(Amount::Raw)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Amount, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Amount::Raw(raw) => serializer.serialize_u64(*raw),
+            Amount::Ui { .. } => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
+/// Converts a decimal UI amount such as "0.5" to atoms of a token with `decimals`, rejecting
+/// amounts more precise than one atom.
+pub fn ui_amount_to_atoms(amount: &str, decimals: u32) -> anyhow::Result<u64> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(anyhow!(
+            "{} has more than the {} decimals of the token",
+            amount,
+            decimals
+        ));
+    }
+    format!("{}{:0<width$}", whole, fraction, width = decimals as usize)
+        .parse()
+        .map_err(|_| anyhow!("{} is too large", amount))
+}
+
+/// Formats atoms of a token with `decimals` as a decimal UI amount without trailing zeros.
+pub fn atoms_to_ui_amount(atoms: u64, decimals: u32) -> String {
+    let unit = 10u128.pow(decimals);
+    let whole = atoms as u128 / unit;
+    let fraction = atoms as u128 % unit;
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    let fraction = format!("{:0>width$}", fraction, width = decimals as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+/// Formats atoms of a token as e.g. "100.5 USDC (100500000 atoms)".
+pub fn format_atoms(atoms: u64, decimals: u32, symbol: &str) -> String {
+    format!(
+        "{} {} ({} atoms)",
+        atoms_to_ui_amount(atoms, decimals),
+        symbol,
+        atoms
+    )
+}
+
+/// Formats quote atoms as e.g. "100.5 USDC (100500000 atoms)".
+pub fn format_quote_atoms(atoms: u64, header: &MarketHeader, ticker: &Ticker) -> String {
+    format_atoms(atoms, header.quote_params.decimals, &ticker.quote)
+}
+
+/// Formats base lots as e.g. "1.5 SOL (1500 lots)".
+pub fn format_base_lots(base_lots: u64, header: &MarketHeader, ticker: &Ticker) -> String {
+    let atoms = base_lots.saturating_mul(header.get_base_lot_size().as_u64());
+    format!(
+        "{} {} ({} lots)",
+        atoms_to_ui_amount(atoms, header.base_params.decimals),
+        ticker.base,
+        base_lots
+    )
+}
+
+/// Formats a price as e.g. "23.45 USDC (23450 ticks)", per base unit of the market.
+pub fn format_price_in_ticks(ticks: u64, header: &MarketHeader, ticker: &Ticker) -> String {
+    let quote_atoms =
+        ticks.saturating_mul(header.get_tick_size_in_quote_atoms_per_base_unit().as_u64());
+    let per_base_unit = match header.raw_base_units_per_base_unit {
+        0 | 1 => String::new(),
+        raw_base_units => format!(" per {} {}", raw_base_units, ticker.base),
+    };
+    format!(
+        "{} {}{} ({} ticks)",
+        atoms_to_ui_amount(quote_atoms, header.quote_params.decimals),
+        ticker.quote,
+        per_base_unit,
+        ticks
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_amounts() {
+        assert_eq!(
+            "100000000".parse::<Amount>().unwrap(),
+            Amount::Raw(100000000)
+        );
+        assert_eq!(
+            "0.5 SOL".parse::<Amount>().unwrap(),
+            Amount::Ui {
+                amount: "0.5".to_string(),
+                symbol: "SOL".to_string()
+            }
+        );
+        assert!("-1 SOL".parse::<Amount>().is_err());
+        assert!(".5 SOL".parse::<Amount>().is_err());
+        assert!("1 SOL USDC".parse::<Amount>().is_err());
+        assert!("0.00 USDC".parse::<Amount>().unwrap().is_zero());
+    }
+
+    #[test]
+    fn test_ui_amount_conversions() {
+        assert_eq!(ui_amount_to_atoms("100", 6).unwrap(), 100_000_000);
+        assert_eq!(ui_amount_to_atoms("0.5", 9).unwrap(), 500_000_000);
+        assert_eq!(ui_amount_to_atoms("1.250000", 2).unwrap(), 125);
+        assert!(ui_amount_to_atoms("0.0000001", 6).is_err());
+        assert!(ui_amount_to_atoms("100000000000000", 9).is_err());
+        assert_eq!(atoms_to_ui_amount(100_500_000, 6), "100.5");
+        assert_eq!(atoms_to_ui_amount(1, 9), "0.000000001");
+        assert_eq!(atoms_to_ui_amount(7_000, 3), "7");
+    }
+}
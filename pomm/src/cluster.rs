@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::fmt;
+use std::str::FromStr;
+
+const MAINNET_BETA_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+const DEVNET_GENESIS_HASH: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
+const TESTNET_GENESIS_HASH: &str = "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY";
+
+/// Solana cluster of an RPC endpoint. Any cluster with an unknown genesis hash is a localnet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Cluster {
+    MainnetBeta,
+    Devnet,
+    Testnet,
+    Localnet,
+}
+
+impl Cluster {
+    pub fn from_genesis_hash(genesis_hash: &str) -> Self {
+        match genesis_hash {
+            MAINNET_BETA_GENESIS_HASH => Cluster::MainnetBeta,
+            DEVNET_GENESIS_HASH => Cluster::Devnet,
+            TESTNET_GENESIS_HASH => Cluster::Testnet,
+            _ => Cluster::Localnet,
+        }
+    }
+
+    /// Asks the RPC endpoint for its genesis hash.
+    pub async fn detect(client: &RpcClient) -> anyhow::Result<Self> {
+        let genesis_hash = client
+            .get_genesis_hash()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to detect the cluster: {}", e))?;
+        Ok(Self::from_genesis_hash(&genesis_hash.to_string()))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cluster::MainnetBeta => "mainnet-beta",
+            Cluster::Devnet => "devnet",
+            Cluster::Testnet => "testnet",
+            Cluster::Localnet => "localnet",
+        }
+    }
+
+    /// Returns the explorer.solana.com link of a transaction. Localnet links point the explorer
+    /// at `rpc_endpoint`.
+    pub fn explorer_tx_url(&self, signature: &str, rpc_endpoint: &str) -> String {
+        match self {
+            Cluster::MainnetBeta => format!("https://explorer.solana.com/tx/{}", signature),
+            Cluster::Devnet | Cluster::Testnet => format!(
+                "https://explorer.solana.com/tx/{}?cluster={}",
+                signature,
+                self.as_str()
+            ),
+            Cluster::Localnet => format!(
+                "https://explorer.solana.com/tx/{}?cluster=custom&customUrl={}",
+                signature,
+                encode_query_value(rpc_endpoint)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet-beta" => Ok(Cluster::MainnetBeta),
+            "devnet" => Ok(Cluster::Devnet),
+            "testnet" => Ok(Cluster::Testnet),
+            "localnet" => Ok(Cluster::Localnet),
+            _ => Err(anyhow::anyhow!(
+                "Invalid cluster({}), expected mainnet-beta, devnet, testnet or localnet",
+                s
+            )),
+        }
+    }
+}
+
+/// Percent-encodes everything but unreserved characters, for use in a query string.
+fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[test]
+fn test_explorer_links() {
+    assert_eq!(
+        Cluster::from_genesis_hash(MAINNET_BETA_GENESIS_HASH).explorer_tx_url("sig", ""),
+        "https://explorer.solana.com/tx/sig"
+    );
+    assert_eq!(
+        Cluster::from_genesis_hash(DEVNET_GENESIS_HASH).explorer_tx_url("sig", ""),
+        "https://explorer.solana.com/tx/sig?cluster=devnet"
+    );
+    assert_eq!(
+        Cluster::from_genesis_hash("11111111111111111111111111111111")
+            .explorer_tx_url("sig", "http://127.0.0.1:8899"),
+        "https://explorer.solana.com/tx/sig?cluster=custom&customUrl=http%3A%2F%2F127.0.0.1%3A8899"
+    );
+}
@@ -1,7 +1,7 @@
 use crate::config::PhoenixOnChainMMConfig;
+use crate::context::PommContext;
 use crate::errors::Error;
-use crate::utils::create_airdrop_spl_ixs;
-use crate::utils::get_pomm_config;
+use crate::utils::{create_airdrop_spl_ixs, ConfigSelection};
 use solana_sdk::signer::Signer;
 use structopt::StructOpt;
 
@@ -9,18 +9,12 @@ use structopt::StructOpt;
 pub struct AirdropBaseAndQuote {}
 
 impl AirdropBaseAndQuote {
-    pub async fn run(&self) -> anyhow::Result<()> {
-        let phoneix_config = get_pomm_config().map_err(|e| Error::from(e.to_string()))?;
-
-        let (_, payer, rpc_enpoint) = phoneix_config
-            .read_global_config()
-            .map_err(|e| Error::from(e.to_string()))?;
-
-        let sdk = phoenix_sdk::sdk_client::SDKClient::new(&payer, &rpc_enpoint)
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = PommContext::load(selection)
             .await
             .map_err(|e| Error::from(e.to_string()))?;
 
-        let PhoenixOnChainMMConfig { market, .. } = phoneix_config.phoenix;
+        let PhoenixOnChainMMConfig { market, .. } = context.market_config();
 
         // To test on devnet, (i) airdrop devnet SOL to the trader account, and (ii) airdrop tokens for the market's base and quote tokens.
         // These instructions only work on devnet.
@@ -34,20 +28,18 @@ impl AirdropBaseAndQuote {
 
         // (ii) Airdrop tokens for the base and quote tokens for the supplied market, used for testing trades.
         // Uses the generic-token-faucet (https://github.com/Ellipsis-Labs/generic-token-faucet).
-        let instructions = create_airdrop_spl_ixs(&sdk, &market, &payer.pubkey())
+        let instructions = create_airdrop_spl_ixs(&context.sdk, market, &context.payer.pubkey())
             .await
             .ok_or(anyhow::anyhow!("empty instruction!"))?;
 
-        let setup_tx = sdk
+        let setup_tx = context
+            .sdk
             .client
             .sign_send_instructions(instructions, vec![])
             .await
             .map_err(|e| Error::from(e.to_string()))?;
 
-        println!(
-            "Setup tx: https://explorer.solana.com/tx/{}?cluster=devnet",
-            setup_tx
-        );
+        println!("Setup tx: {}", context.explorer_tx_url(&setup_tx));
 
         Ok(())
     }
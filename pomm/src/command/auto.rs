@@ -1,6 +1,7 @@
 use crate::config::Config as PhoenixConfig;
 use crate::constant::DEFAULT_CONFIG_FILE;
 use crate::errors::Error;
+use crate::utils::{get_config_path, ConfigSelection};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -12,27 +13,26 @@ pub struct Auto {
 }
 
 impl Auto {
-    pub fn run(&self) -> anyhow::Result<PathBuf> {
+    pub fn run(&self, selection: &ConfigSelection) -> anyhow::Result<PathBuf> {
         if let Some(config_path) = self.config_path.clone() {
             println!("enpter input config file");
             let config_str = std::fs::read_to_string(config_path.clone())
                 .map_err(|e| Error::from(e.to_string()))?;
-            assert!(toml::from_str::<PhoenixConfig>(&config_str).is_ok());
+            PhoenixConfig::from_toml_str(&config_str, None)?;
             Ok(config_path)
         } else {
-            // open  config file path is  ~/.config/pomm/config.toml
-            let home_path = dirs::home_dir().ok_or(anyhow::anyhow!("can't open home dir"))?;
-            let pomm_config_path = home_path.join(".config").join("pomm");
-            let config_path = pomm_config_path.join("config.toml");
+            // open the --config file, by default ~/.config/pomm/config.toml
+            let config_path = get_config_path(selection)?;
             if std::fs::read_to_string(config_path.clone()).is_ok() {
                 Ok(config_path)
             } else {
-                std::fs::create_dir_all(pomm_config_path.clone())?;
-                let config_path = pomm_config_path.join("config.toml");
+                if let Some(pomm_config_path) = config_path.parent() {
+                    std::fs::create_dir_all(pomm_config_path)?;
+                }
                 std::fs::write(config_path.clone(), DEFAULT_CONFIG_FILE)?;
                 let config_str = std::fs::read_to_string(config_path.clone())
                     .map_err(|e| Error::from(e.to_string()))?;
-                assert!(toml::from_str::<PhoenixConfig>(&config_str).is_ok());
+                PhoenixConfig::from_toml_str(&config_str, None)?;
                 Ok(config_path)
             }
         }
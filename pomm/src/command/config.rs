@@ -0,0 +1,78 @@
+use crate::config::ConfigSource;
+use crate::errors::Error;
+use crate::utils::{get_config_path, get_pomm_config, ConfigSelection};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum ConfigCommand {
+    /// print every resolved config value and where it comes from
+    #[structopt(name = "show")]
+    Show,
+}
+
+impl ConfigCommand {
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        match self {
+            ConfigCommand::Show => show(selection),
+        }
+    }
+}
+
+fn show(selection: &ConfigSelection) -> anyhow::Result<()> {
+    let phoneix_config = get_pomm_config(selection).map_err(|e| Error::from(e.to_string()))?;
+
+    println!("# {}", get_config_path(selection)?.display());
+
+    let mut lines = vec![];
+    // Unset global values come from the Solana CLI config
+    let (commitment, keypair_path, rpc_endpoint) = phoneix_config.resolve_global_config();
+    for (key, value) in [
+        ("commitment", commitment),
+        ("keypair_path", keypair_path),
+        ("rpc_endpoint", rpc_endpoint),
+    ] {
+        let source = match phoneix_config.get_source(key) {
+            ConfigSource::Default => ConfigSource::SolanaCli,
+            source => source,
+        };
+        lines.push((format!("{} = {}", key, toml::Value::String(value)), source));
+    }
+
+    let mut resolved = toml::Table::try_from(&phoneix_config)?;
+    for key in ["commitment", "keypair_path", "rpc_endpoint"] {
+        resolved.remove(key);
+    }
+    let mut values = vec![];
+    flatten("", &resolved, &mut values);
+    for (key, value) in values {
+        let source = phoneix_config.get_source(&key);
+        lines.push((format!("{} = {}", key, value), source));
+    }
+
+    let width = lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0);
+    for (line, source) in lines {
+        println!("{:width$}  # {}", line, source, width = width);
+    }
+
+    Ok(())
+}
+
+/// Lists the values of `table` under dotted keys, entries of arrays of tables keyed by index.
+fn flatten(prefix: &str, table: &toml::Table, values: &mut Vec<(String, toml::Value)>) {
+    for (key, value) in table {
+        let key = format!("{}{}", prefix, key);
+        match value {
+            toml::Value::Table(table) => flatten(&format!("{}.", key), table, values),
+            toml::Value::Array(array)
+                if !array.is_empty() && array.iter().all(|v| v.is_table()) =>
+            {
+                for (index, value) in array.iter().enumerate() {
+                    if let toml::Value::Table(table) = value {
+                        flatten(&format!("{}.{}.", key, index), table, values)
+                    }
+                }
+            }
+            value => values.push((key, value.clone())),
+        }
+    }
+}
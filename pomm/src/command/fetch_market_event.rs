@@ -1,9 +1,6 @@
-use crate::config::PhoenixOnChainMMConfig;
+use crate::context::PommContext;
 use crate::errors::Error;
-use crate::utils::get_pomm_config;
-use ellipsis_client::EllipsisClient;
-use phoenix_sdk::sdk_client::SDKClient;
-use solana_client::nonblocking::rpc_client::RpcClient;
+use crate::utils::ConfigSelection;
 use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::signature::Signature;
@@ -19,25 +16,14 @@ pub struct FetchMarketEvent {
 }
 
 impl FetchMarketEvent {
-    pub async fn run(&self) -> anyhow::Result<()> {
-        let phoneix_config = get_pomm_config().map_err(|e| Error::from(e.to_string()))?;
-
-        let (commitment, payer, rpc_enpoint) = phoneix_config
-            .read_global_config()
-            .map_err(|e| Error::from(e.to_string()))?;
-
-        let client = EllipsisClient::from_rpc(
-            RpcClient::new_with_commitment(rpc_enpoint, commitment),
-            &payer,
-        )?;
-
-        let sdk_client = Arc::new(
-            SDKClient::new_from_ellipsis_client(client)
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = Arc::new(
+            PommContext::load(selection)
                 .await
                 .map_err(|e| Error::from(e.to_string()))?,
         );
 
-        let PhoenixOnChainMMConfig { market, .. } = phoneix_config.phoenix;
+        let market = context.market_config().market;
 
         let mut until = None;
         loop {
@@ -56,8 +42,8 @@ impl FetchMarketEvent {
                 },
             };
 
-            let signatures = sdk_client
-                .client
+            let signatures = context
+                .client()
                 .get_signatures_for_address_with_config(&market, config)
                 .await
                 .unwrap_or_default()
@@ -73,11 +59,10 @@ impl FetchMarketEvent {
             let mut handles = vec![];
 
             for signature in signatures {
-                let sdk = sdk_client.clone();
-                let handle =
-                    tokio::spawn(
-                        async move { sdk.parse_events_from_transaction(&signature).await },
-                    );
+                let context = context.clone();
+                let handle = tokio::spawn(async move {
+                    context.sdk.parse_events_from_transaction(&signature).await
+                });
                 handles.push(handle);
             }
 
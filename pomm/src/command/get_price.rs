@@ -1,8 +1,12 @@
-use crate::constant::BASE;
+use crate::amount::format_price_in_ticks;
+use crate::context::PommContext;
 use crate::errors::Error;
-use crate::utils::get_pomm_config;
+use crate::utils::ConfigSelection;
+use phoenix_onchain_mm::phoenix_v1::get_market_params;
+use phoenix_onchain_mm::price::{
+    ask_price_in_ticks, bid_price_in_ticks, fair_price_in_ticks, FixedPrice,
+};
 use pyth_sdk_solana::load_price_feed_from_account;
-use solana_client::nonblocking::rpc_client::RpcClient;
 use std::time::{SystemTime, UNIX_EPOCH};
 use structopt::StructOpt;
 
@@ -10,26 +14,17 @@ use structopt::StructOpt;
 pub struct GetPrice {}
 
 impl GetPrice {
-    pub async fn run(&self) -> anyhow::Result<()> {
-        let phoneix_config = get_pomm_config().map_err(|e| Error::from(e.to_string()))?;
-
-        let (commitment, payer, rpc_enpoint) = phoneix_config
-            .read_global_config()
-            .map_err(|e| Error::from(e.to_string()))?;
-
-        let client = RpcClient::new_with_commitment(rpc_enpoint.to_string(), commitment);
-
-        let _sdk = phoenix_sdk::sdk_client::SDKClient::new(&payer, &rpc_enpoint)
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = PommContext::load(selection)
             .await
             .map_err(|e| Error::from(e.to_string()))?;
+        let client = context.client();
 
-        let base_account = phoneix_config
-            .phoenix
-            .get_base_oracle_account()
+        let base_account = context
+            .get_base_oracle_account(context.market_config())
             .map_err(|e| Error::from(e.to_string()))?;
-        let quote_account = phoneix_config
-            .phoenix
-            .get_quote_oracle_account()
+        let quote_account = context
+            .get_quote_oracle_account(context.market_config())
             .map_err(|e| Error::from(e.to_string()))?;
 
         // get price data from key
@@ -49,11 +44,11 @@ impl GetPrice {
             .get_price_no_older_than(current_time, 60)
             .ok_or(anyhow::anyhow!("base price is unavaiable"))?;
 
-        let result = base_price.price as f64 * BASE.powi(base_price.expo);
+        let base_fixed_price = FixedPrice::from_pyth(base_price.price, base_price.expo)?;
 
         println!(
             "Base price ........... {} x 10^{} = {}",
-            base_price.price, base_price.expo, result
+            base_price.price, base_price.expo, base_fixed_price
         );
 
         // get price data from key
@@ -72,11 +67,33 @@ impl GetPrice {
             .ok_or(anyhow::anyhow!("base price is unavaiable"))
             .map_err(|e| Error::from(e.to_string()))?;
 
-        let result = quote_price.price as f64 * BASE.powi(quote_price.expo);
+        let quote_fixed_price = FixedPrice::from_pyth(quote_price.price, quote_price.expo)?;
 
         println!(
             "Quote price ........... {} x 10^{} = {}",
-            quote_price.price, quote_price.expo, result
+            quote_price.price, quote_price.expo, quote_fixed_price
+        );
+
+        // Same math as the program, so the ticks match what `update-quotes` would place
+        // before any book-based adjustment
+        let header = &context
+            .get_market_header(&context.market_config().market)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+        let market_params = get_market_params(header)?;
+        let fair_price = fair_price_in_ticks(base_fixed_price, quote_fixed_price, &market_params)?;
+        let edge_in_bps = context.market_config().quote_edge_in_bps;
+        let ticker = &context.market_config().ticker;
+
+        println!(
+            "Fair price ........... {}",
+            format_price_in_ticks(fair_price, header, ticker)
+        );
+        println!(
+            "Oracle quotes ........ {} @ {} ({} bps edge)",
+            format_price_in_ticks(bid_price_in_ticks(fair_price, edge_in_bps)?, header, ticker),
+            format_price_in_ticks(ask_price_in_ticks(fair_price, edge_in_bps)?, header, ticker),
+            edge_in_bps
         );
 
         Ok(())
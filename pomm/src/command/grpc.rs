@@ -1,7 +1,7 @@
+use crate::context::PommContext;
 use crate::errors::Error;
-use crate::utils::get_pomm_config;
+use crate::utils::ConfigSelection;
 use ellipsis_client::grpc_client::transaction_subscribe;
-use phoenix_sdk::sdk_client::SDKClient;
 use solana_sdk::pubkey::Pubkey;
 use structopt::StructOpt;
 use tokio::{sync::mpsc::channel, try_join};
@@ -21,16 +21,11 @@ pub struct Grpc {
 }
 
 impl Grpc {
-    pub async fn run(&self) -> anyhow::Result<()> {
-        let phoneix_config = get_pomm_config().map_err(|e| Error::from(e.to_string()))?;
-
-        let (_, payer, rpc_enpoint) = phoneix_config
-            .read_global_config()
-            .map_err(|e| Error::from(e.to_string()))?;
-
-        let sdk = SDKClient::new(&payer, &rpc_enpoint)
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let PommContext { sdk, cluster, .. } = PommContext::load(selection)
             .await
             .map_err(|e| Error::from(e.to_string()))?;
+        let rpc_enpoint = cluster.rpc_endpoint;
 
         let (sender, mut receiver) = channel(10000);
 
@@ -1,9 +1,8 @@
+use crate::amount::format_atoms;
+use crate::context::PommContext;
 use crate::errors::Error;
-use crate::utils::get_pomm_config;
-use phoenix::program::MarketHeader;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signer::Signer;
-use spl_associated_token_account::get_associated_token_address;
+use crate::utils::ConfigSelection;
+use solana_account_decoder::parse_token::UiTokenAmount;
 use std::io;
 use std::io::Write;
 use structopt::StructOpt;
@@ -17,40 +16,29 @@ pub struct ListenBalance {
 }
 
 impl ListenBalance {
-    pub async fn run(&self) -> anyhow::Result<()> {
-        let phoneix_config = get_pomm_config().map_err(|e| Error::from(e.to_string()))?;
-
-        let (commitment, payer, rpc_enpoint) = phoneix_config
-            .read_global_config()
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = PommContext::load(selection)
+            .await
             .map_err(|e| Error::from(e.to_string()))?;
+        let client = context.client();
 
-        let client = RpcClient::new_with_commitment(rpc_enpoint.to_string(), commitment);
-
-        let data = client
-            .get_account_data(&phoneix_config.phoenix.market)
-            .await?;
-        let header =
-            bytemuck::try_from_bytes::<MarketHeader>(&data[..std::mem::size_of::<MarketHeader>()])
-                .map_err(|_| anyhow::Error::msg("Failed to parse Phoenix market header"))?;
+        let (base_token_account, quote_token_account) =
+            context.get_token_accounts(context.market_config()).await?;
 
-        let quote_token_account =
-            get_associated_token_address(&payer.pubkey(), &header.quote_params.mint_key);
-        let base_token_account =
-            get_associated_token_address(&payer.pubkey(), &header.base_params.mint_key);
+        let ticker = &context.market_config().ticker;
 
         let quote_start_balance = client
             .get_token_account_balance(&quote_token_account)
-            .await?
-            .ui_amount_string;
+            .await?;
 
         let base_start_balance = client
             .get_token_account_balance(&base_token_account)
-            .await?
-            .ui_amount_string;
+            .await?;
 
         println!(
-            "Base Balance: {:#?}, QuoteBalance: {:#?}\n",
-            base_start_balance, quote_start_balance
+            "Base Balance: {}, QuoteBalance: {}\n",
+            format_balance(&base_start_balance, &ticker.base)?,
+            format_balance(&quote_start_balance, &ticker.quote)?
         );
 
         io::stdout().flush()?;
@@ -58,17 +46,16 @@ impl ListenBalance {
         loop {
             let quote_balance = client
                 .get_token_account_balance(&quote_token_account)
-                .await?
-                .ui_amount_string;
+                .await?;
 
             let base_balance = client
                 .get_token_account_balance(&base_token_account)
-                .await?
-                .ui_amount_string;
+                .await?;
 
             println!(
                 "\tCurrent Base Balance: {}, Current Quote Balance: {}",
-                base_balance, quote_balance
+                format_balance(&base_balance, &ticker.base)?,
+                format_balance(&quote_balance, &ticker.quote)?
             );
 
             io::stdout().flush()?;
@@ -77,3 +64,11 @@ impl ListenBalance {
         }
     }
 }
+
+fn format_balance(balance: &UiTokenAmount, symbol: &str) -> anyhow::Result<String> {
+    Ok(format_atoms(
+        balance.amount.parse()?,
+        balance.decimals as u32,
+        symbol,
+    ))
+}
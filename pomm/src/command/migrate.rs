@@ -0,0 +1,26 @@
+use crate::context::PommContext;
+use crate::errors::Error;
+use crate::utils::ConfigSelection;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct Migrate {}
+
+impl Migrate {
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = PommContext::load(selection)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        let ix = context.migrate_ix(context.market_config());
+
+        let txid = context
+            .send_instructions(&[ix])
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        println!("Migrated strategy: {}", context.explorer_tx_url(&txid));
+
+        Ok(())
+    }
+}
@@ -1,25 +1,53 @@
+pub mod accept_admin;
 pub mod airdrop_base_and_quote;
 pub mod auto;
 pub mod cancle;
+pub mod config;
 pub mod fetch_market_event;
 pub mod get_market_address;
 pub mod get_price;
 pub mod grpc;
+pub mod halt;
 pub mod initialize;
 pub mod list_market;
 pub mod listen_balance;
+pub mod migrate;
+pub mod oracles;
+pub mod preview_quotes;
+pub mod propose_admin;
+pub mod reconcile;
+pub mod reset_circuit_breaker;
+pub mod set_circuit_breaker;
+pub mod set_guardian;
+pub mod set_schedule;
+pub mod set_trader;
 pub mod update_quotes;
 pub mod validate;
 pub mod view_state_order_book;
 
+use crate::utils::ConfigSelection;
+use accept_admin::AcceptAdmin;
 use airdrop_base_and_quote::AirdropBaseAndQuote;
 use auto::Auto;
 use cancle::Cancle;
+use config::ConfigCommand;
 use fetch_market_event::FetchMarketEvent;
 use get_market_address::GetMarketAddress;
 use get_price::GetPrice;
+use halt::Halt;
 use initialize::Initialize;
 use listen_balance::ListenBalance;
+use migrate::Migrate;
+use oracles::OraclesCommand;
+use preview_quotes::PreviewQuotes;
+use propose_admin::ProposeAdmin;
+use reconcile::Reconcile;
+use reset_circuit_breaker::ResetCircuitBreaker;
+use set_circuit_breaker::SetCircuitBreaker;
+use set_guardian::SetGuardian;
+use set_schedule::SetSchedule;
+use set_trader::SetTrader;
+use std::path::PathBuf;
 use structopt::StructOpt;
 use update_quotes::UpdateQuotes;
 use view_state_order_book::ViewStateOrderBook;
@@ -32,10 +60,43 @@ pub enum Command {
     /// initialize Phoenix onchain Maket Maker and Claim Market Sate
     #[structopt(name = "init")]
     Initialize(Initialize),
+    /// upgrade a strategy created before the admin, trader and guardian roles
+    #[structopt(name = "migrate")]
+    Migrate(Migrate),
     /// update quotes
     #[structopt(name = "update-quotes")]
     UpdateQuotes(UpdateQuotes),
-    /// cancle all orders
+    /// preview the quotes the program would place, without sending a transaction
+    #[structopt(name = "preview-quotes")]
+    PreviewQuotes(PreviewQuotes),
+    /// clear a tripped circuit breaker so the strategy quotes again
+    #[structopt(name = "reset-circuit-breaker")]
+    ResetCircuitBreaker(ResetCircuitBreaker),
+    /// push the circuit breaker limits from the config on chain, signed by the admin
+    #[structopt(name = "set-circuit-breaker")]
+    SetCircuitBreaker(SetCircuitBreaker),
+    /// cancel resting orders of the trader that the strategy does not track
+    #[structopt(name = "reconcile")]
+    Reconcile(Reconcile),
+    /// halt the strategy, signed by the guardian or the admin
+    #[structopt(name = "halt")]
+    Halt(Halt),
+    /// start transferring the strategy admin role to another key
+    #[structopt(name = "propose-admin")]
+    ProposeAdmin(ProposeAdmin),
+    /// accept a pending admin transfer, signed by the proposed admin
+    #[structopt(name = "accept-admin")]
+    AcceptAdmin(AcceptAdmin),
+    /// change the key that places the quotes
+    #[structopt(name = "set-trader")]
+    SetTrader(SetTrader),
+    /// change the key allowed to halt the strategy
+    #[structopt(name = "set-guardian")]
+    SetGuardian(SetGuardian),
+    /// push the [[phoenix.schedule]] windows from the config on chain, signed by the admin
+    #[structopt(name = "set-schedule")]
+    SetSchedule(SetSchedule),
+    /// cancle all orders through the strategy
     #[structopt(name = "cancle")]
     Cancle(Cancle),
     /// listen balance
@@ -65,35 +126,78 @@ pub enum Command {
     /// validate config
     #[structopt(name = "validate")]
     Validate(validate::Validate),
+    /// inspect the resolved config
+    #[structopt(name = "config")]
+    Config(ConfigCommand),
+    /// inspect the oracle registry
+    #[structopt(name = "oracles")]
+    Oracles(OraclesCommand),
 }
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "pomm")]
 pub struct PhoneixOnChainMMCli {
+    /// config file to use instead of ~/.config/pomm/config.toml
+    #[structopt(long, global = true, parse(from_os_str))]
+    pub config: Option<PathBuf>,
+    /// profile to use from the [profiles] table of the config file
+    #[structopt(long, global = true)]
+    pub profile: Option<String>,
+    /// override a config value, e.g. --set phoenix.quote_edge_in_bps=5, takes precedence over POMM_* variables
+    #[structopt(long = "set", global = true, number_of_values = 1)]
+    pub overrides: Vec<String>,
     #[structopt(subcommand)]
     pub command: Command,
 }
 
 impl PhoneixOnChainMMCli {
     pub async fn run(&self) -> anyhow::Result<()> {
+        let selection = &ConfigSelection {
+            config_path: self.config.clone(),
+            profile: self.profile.clone(),
+            overrides: self.overrides.clone(),
+        };
         match &self.command {
             Command::Auto(auto) => {
-                let config_path = auto.run();
+                let config_path = auto.run(selection);
                 println!("ConfigPath: {:?}", config_path);
                 Ok(())
             }
-            Command::Initialize(initialize) => initialize.run().await,
-            Command::UpdateQuotes(update_quotes) => update_quotes.run().await,
-            Command::Cancle(cancle) => cancle.run().await,
-            Command::ListenBalance(listen_balance) => listen_balance.run().await,
-            Command::AirDropBaseAndQuote(airdrop) => airdrop.run().await,
-            Command::FetchMarketEvent(fetch_market_event) => fetch_market_event.run().await,
-            Command::Grpc(grpc) => grpc.run().await,
-            Command::ViewStateOrderBook(view_state_order_book) => view_state_order_book.run().await,
-            Command::ListMarket(list_market) => list_market.run().await,
-            Command::GetMarketAddress(get_market_address) => get_market_address.run().await,
-            Command::GetPrice(get_price) => get_price.run().await,
-            Command::Validate(validate) => validate.run().await,
+            Command::Initialize(initialize) => initialize.run(selection).await,
+            Command::Migrate(migrate) => migrate.run(selection).await,
+            Command::UpdateQuotes(update_quotes) => update_quotes.run(selection).await,
+            Command::PreviewQuotes(preview_quotes) => preview_quotes.run(selection).await,
+            Command::ResetCircuitBreaker(reset_circuit_breaker) => {
+                reset_circuit_breaker.run(selection).await
+            }
+            Command::SetCircuitBreaker(set_circuit_breaker) => {
+                set_circuit_breaker.run(selection).await
+            }
+            Command::Reconcile(reconcile) => reconcile.run(selection).await,
+            Command::Halt(halt) => halt.run(selection).await,
+            Command::ProposeAdmin(propose_admin) => propose_admin.run(selection).await,
+            Command::AcceptAdmin(accept_admin) => accept_admin.run(selection).await,
+            Command::SetTrader(set_trader) => set_trader.run(selection).await,
+            Command::SetGuardian(set_guardian) => set_guardian.run(selection).await,
+            Command::SetSchedule(set_schedule) => set_schedule.run(selection).await,
+            Command::Cancle(cancle) => cancle.run(selection).await,
+            Command::ListenBalance(listen_balance) => listen_balance.run(selection).await,
+            Command::AirDropBaseAndQuote(airdrop) => airdrop.run(selection).await,
+            Command::FetchMarketEvent(fetch_market_event) => {
+                fetch_market_event.run(selection).await
+            }
+            Command::Grpc(grpc) => grpc.run(selection).await,
+            Command::ViewStateOrderBook(view_state_order_book) => {
+                view_state_order_book.run(selection).await
+            }
+            Command::ListMarket(list_market) => list_market.run(selection).await,
+            Command::GetMarketAddress(get_market_address) => {
+                get_market_address.run(selection).await
+            }
+            Command::GetPrice(get_price) => get_price.run(selection).await,
+            Command::Validate(validate) => validate.run(selection).await,
+            Command::Config(config) => config.run(selection).await,
+            Command::Oracles(oracles) => oracles.run(selection).await,
         }
     }
 }
@@ -0,0 +1,56 @@
+use crate::context::PommContext;
+use crate::errors::Error;
+use crate::utils::{get_oracle_registry_path, ConfigSelection};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum OraclesCommand {
+    /// print every oracle of the registry, the built-in ones and those of oracles.toml
+    #[structopt(name = "list")]
+    List,
+}
+
+impl OraclesCommand {
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        match self {
+            OraclesCommand::List => list(selection).await,
+        }
+    }
+}
+
+async fn list(selection: &ConfigSelection) -> anyhow::Result<()> {
+    let context = PommContext::load(selection)
+        .await
+        .map_err(|e| Error::from(e.to_string()))?;
+    let registry = &context.oracle_registry;
+
+    println!("# {}", get_oracle_registry_path(selection)?.display());
+    println!("# Resolving oracles on {}", context.cluster.cluster);
+
+    let rows = registry
+        .entries()
+        .map(|(symbol, cluster, entry)| {
+            (
+                symbol.to_uppercase(),
+                cluster.to_string(),
+                entry.account.to_string(),
+                entry.source.to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let symbol_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+    let cluster_width = rows.iter().map(|row| row.1.len()).max().unwrap_or(0);
+    for (symbol, cluster, account, source) in rows {
+        println!(
+            "{:symbol_width$}  {:cluster_width$}  {:44}  # {}",
+            symbol,
+            cluster,
+            account,
+            source,
+            symbol_width = symbol_width,
+            cluster_width = cluster_width
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use crate::context::PommContext;
+use crate::errors::Error;
+use crate::utils::ConfigSelection;
+use anchor_lang::AnchorDeserialize;
+use phoenix_onchain_mm::client;
+use phoenix_onchain_mm::QuotePreview;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct PreviewQuotes {}
+
+impl PreviewQuotes {
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = PommContext::load(selection)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+        let client = context.client();
+        let market_config = context.market_config();
+        let payer = &context.payer;
+
+        // The preview uses the parameters from the config file instead of the stored ones,
+        // so a new parameter set can be checked before it goes live
+        let params = context
+            .get_strategy_params(market_config)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        let strategy_key = context.get_strategy_address(market_config);
+
+        let ix = client::preview_quotes_ix_with_program_id(
+            &context.cluster.program_id,
+            &strategy_key,
+            &payer.pubkey(),
+            &market_config.market,
+            params,
+        );
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[payer],
+            client
+                .get_latest_blockhash()
+                .await
+                .map_err(|e| Error::from(e.to_string()))?,
+        );
+
+        let result = client
+            .simulate_transaction(&transaction)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?
+            .value;
+
+        if let Some(err) = result.err {
+            for log in result.logs.unwrap_or_default() {
+                println!("{}", log);
+            }
+            return Err(anyhow::anyhow!("Preview quotes failed: {:?}", err));
+        }
+
+        let (return_data, _encoding) = result
+            .return_data
+            .ok_or(anyhow::anyhow!("preview quotes returned no data"))?
+            .data;
+        let return_data = base64::decode(return_data).map_err(|e| Error::from(e.to_string()))?;
+        let preview = QuotePreview::try_from_slice(&return_data)?;
+
+        println!("Quote Params: {:#?}", params);
+        println!(
+            "Fair price ........... {} ticks",
+            preview.fair_price_in_ticks
+        );
+        println!(
+            "Oracle price ......... {} ticks",
+            preview.oracle_price_in_ticks
+        );
+        println!(
+            "Microprice ........... {} ticks",
+            preview.microprice_in_ticks
+        );
+        println!("Edge ................. {} bps", preview.edge_in_bps);
+        // The variance is stored in millionths of bps² per second
+        println!(
+            "Volatility ........... {:.3} bps per second^1/2",
+            (preview.return_variance_in_micro_bps_squared_per_second as f64 / 1e6).sqrt()
+        );
+        println!(
+            "Current market ....... {} @ {}",
+            preview.best_bid_in_ticks, preview.best_ask_in_ticks
+        );
+        println!(
+            "Our market ........... {} {} @ {} {}",
+            preview.bid_size_in_base_lots,
+            preview.bid_price_in_ticks,
+            preview.ask_price_in_ticks,
+            preview.ask_size_in_base_lots
+        );
+
+        Ok(())
+    }
+}
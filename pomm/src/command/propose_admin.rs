@@ -0,0 +1,41 @@
+use crate::context::PommContext;
+use crate::errors::Error;
+use crate::utils::ConfigSelection;
+use phoenix_onchain_mm::client;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::Signer;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct ProposeAdmin {
+    /// Key that has to accept the transfer with `pomm accept-admin`
+    #[structopt(parse(try_from_str = Pubkey::from_str))]
+    pub new_admin: Pubkey,
+}
+
+impl ProposeAdmin {
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = PommContext::load(selection)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        let strategy_key = context.get_strategy_address(context.market_config());
+
+        let ix = client::propose_admin_ix_with_program_id(
+            &context.cluster.program_id,
+            &strategy_key,
+            &context.payer.pubkey(),
+            &self.new_admin,
+        );
+
+        let txid = context
+            .send_instructions(&[ix])
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        println!("Propose admin: {}", context.explorer_tx_url(&txid));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use crate::context::PommContext;
+use crate::errors::Error;
+use crate::utils::ConfigSelection;
+use phoenix_onchain_mm::client;
+use solana_sdk::signer::Signer;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct ResetCircuitBreaker {}
+
+impl ResetCircuitBreaker {
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = PommContext::load(selection)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        let strategy_key = context.get_strategy_address(context.market_config());
+
+        let ix = client::reset_circuit_breaker_ix_with_program_id(
+            &context.cluster.program_id,
+            &strategy_key,
+            &context.payer.pubkey(),
+        );
+
+        let txid = context
+            .send_instructions(&[ix])
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        println!("Reset circuit breaker: {}", context.explorer_tx_url(&txid));
+
+        Ok(())
+    }
+}
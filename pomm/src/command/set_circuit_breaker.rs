@@ -0,0 +1,34 @@
+use crate::context::PommContext;
+use crate::errors::Error;
+use crate::utils::ConfigSelection;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct SetCircuitBreaker {}
+
+impl SetCircuitBreaker {
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = PommContext::load(selection)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        let params = context
+            .get_circuit_breaker_params(context.market_config())
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+        let ix = context.set_circuit_breaker_ix(context.market_config(), params);
+
+        let txid = context
+            .send_instructions(&[ix])
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        println!(
+            "Set circuit breaker limits {:?}: {}",
+            params,
+            context.explorer_tx_url(&txid)
+        );
+
+        Ok(())
+    }
+}
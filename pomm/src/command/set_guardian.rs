@@ -0,0 +1,41 @@
+use crate::context::PommContext;
+use crate::errors::Error;
+use crate::utils::ConfigSelection;
+use phoenix_onchain_mm::client;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::Signer;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct SetGuardian {
+    /// Key allowed to halt the strategy, 11111111111111111111111111111111 removes the guardian
+    #[structopt(parse(try_from_str = Pubkey::from_str))]
+    pub new_guardian: Pubkey,
+}
+
+impl SetGuardian {
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = PommContext::load(selection)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        let strategy_key = context.get_strategy_address(context.market_config());
+
+        let ix = client::set_guardian_ix_with_program_id(
+            &context.cluster.program_id,
+            &strategy_key,
+            &context.payer.pubkey(),
+            &self.new_guardian,
+        );
+
+        let txid = context
+            .send_instructions(&[ix])
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        println!("Set guardian: {}", context.explorer_tx_url(&txid));
+
+        Ok(())
+    }
+}
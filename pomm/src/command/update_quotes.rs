@@ -1,161 +1,380 @@
 use crate::config::PhoenixOnChainMMConfig;
 use crate::constant::BASE;
-use crate::constant::{PHOENIX_ONCHAIN_MM_ORACLE_SEED, PHOENIX_ONCHAIN_MM_STRATEGY_SEED};
+use crate::context::PommContext;
 use crate::errors::Error;
-use crate::ids;
-use crate::utils::get_pomm_config;
-use anchor_lang::InstructionData;
-use anchor_lang::ToAccountMetas;
-use phoenix::program::get_seat_address;
-use phoenix::program::get_vault_address;
-use phoenix::program::MarketHeader;
-use phoenix_onchain_mm::accounts::UpdateQuotes as UpdateQuotesAccounts;
-use phoenix_onchain_mm::instruction::UpdateQuotes as UpdateQuotesInstruction;
-use phoenix_onchain_mm::oracle::OracleConfig;
-use phoenix_onchain_mm::OrderParams;
-use phoenix_onchain_mm::PriceImprovementBehavior;
-use phoenix_onchain_mm::StrategyParams;
-use phoenix_sdk::sdk_client::SDKClient;
+use crate::utils::{check_market_config, ConfigSelection};
+use phoenix::program::create_withdraw_funds_instruction_with_custom_token_accounts;
 use pyth_sdk_solana::load_price_feed_from_account;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::instruction::Instruction;
+use solana_sdk::hash::Hash;
 use solana_sdk::signer::Signer;
 use solana_sdk::transaction::Transaction;
-use spl_associated_token_account::get_associated_token_address;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use structopt::StructOpt;
+use tokio::signal;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+
+/// How long a fetched blockhash is reused before asking the RPC for a new one
+const BLOCKHASH_MAX_AGE: Duration = Duration::from_secs(5);
+/// Longest delay before a failed task is restarted
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
 
 #[derive(Debug, StructOpt)]
 pub struct UpdateQuotes {
     #[structopt(long, default_value = "60")]
     pub rebalance_sec: u64,
+    /// Also withdraw the free funds of every market on shutdown
+    #[structopt(long)]
+    pub withdraw_on_exit: bool,
+    /// Time given to stop the tasks and cancel the quotes on Ctrl-C or SIGTERM
+    #[structopt(long, default_value = "30")]
+    pub shutdown_timeout_sec: u64,
 }
 
 impl UpdateQuotes {
     // TODO: It should automatically rebalance and be able to maintain 24/7 liquidity.
-    pub async fn run(&self) -> anyhow::Result<()> {
-        // 创建一个异步任务线程执行定时器任务
-        let update_quote_task_handle = tokio::spawn(update_quote());
+    /// Quotes every configured market, each with its own quote and rebalance task, until
+    /// Ctrl-C or SIGTERM. The quotes are then cancelled, and it fails if they could not be.
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        // One RPC client, shared by our own transactions and the Phoenix SDK
+        let mut context = PommContext::load(selection)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
 
-        // 创建一个异步任务线程执行另一个任务
-        let rebalance_task_handle = tokio::spawn(rebalance_task(self.rebalance_sec));
+        let markets = context.config.get_markets();
+        for market_config in markets.iter() {
+            check_market_config(&context, market_config).await?;
+            context.sdk.add_market(&market_config.market).await?;
+        }
+        let shared = Arc::new(SharedClients {
+            context,
+            blockhash_cache: BlockhashCache::default(),
+        });
+
+        let (shutdown_sender, shutdown) = watch::channel(false);
+        let mut handles = vec![];
+        for market_config in markets.iter().cloned() {
+            let name = format!("{} ({})", market_config.ticker, market_config.market);
+            handles.push(tokio::spawn(supervise(
+                format!("{} quotes", name),
+                shutdown.clone(),
+                {
+                    let market_config = market_config.clone();
+                    let shared = shared.clone();
+                    let shutdown = shutdown.clone();
+                    move || update_quote(market_config.clone(), shared.clone(), shutdown.clone())
+                },
+            )));
+            let rebalance_sec = self.rebalance_sec;
+            handles.push(tokio::spawn(supervise(
+                format!("{} rebalance", name),
+                shutdown.clone(),
+                {
+                    let shared = shared.clone();
+                    let shutdown = shutdown.clone();
+                    move || {
+                        rebalance_task(
+                            market_config.clone(),
+                            shared.clone(),
+                            rebalance_sec,
+                            shutdown.clone(),
+                        )
+                    }
+                },
+            )));
+        }
 
-        // 等待异步任务执行完成
-        let (_v1, _v2) = tokio::join!(update_quote_task_handle, rebalance_task_handle);
+        shutdown_signal().await?;
+        println!("Shutting down, cancelling the quotes of every market");
+        // Tasks stop before their next transaction, so no quote lands after the cancel
+        shutdown_sender.send(true)?;
+        let shutdown_timeout = Duration::from_secs(self.shutdown_timeout_sec);
+        let deadline = tokio::time::Instant::now() + shutdown_timeout;
+        // Keep at least half of the timeout for the cancel transactions
+        let stopped = tokio::time::timeout(shutdown_timeout / 2, async {
+            for handle in handles.iter_mut().filter(|handle| !handle.is_finished()) {
+                let _ = handle.await;
+            }
+        })
+        .await;
+        if stopped.is_err() {
+            // A task still running could place a quote after the cancel, stop it first
+            println!("Some tasks did not stop in time, aborting them");
+            let running = handles
+                .into_iter()
+                .filter(|handle| !handle.is_finished())
+                .collect::<Vec<_>>();
+            for handle in running.iter() {
+                handle.abort();
+            }
+            for handle in running {
+                let _ = handle.await;
+            }
+        }
 
-        Ok(())
+        tokio::time::timeout_at(
+            deadline,
+            cancel_all_quotes(&shared.context, &markets, self.withdraw_on_exit),
+        )
+        .await
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "Cleanup did not finish within {}s, quotes may still be on the book",
+                self.shutdown_timeout_sec
+            )
+        })?
     }
 }
 
-async fn rebalance_task(reblance_sec: u64) -> anyhow::Result<()> {
-    let phoneix_config = get_pomm_config().map_err(|e| Error::from(e.to_string()))?;
+/// Resolves on Ctrl-C, or on SIGTERM such as when a container is stopped
+async fn shutdown_signal() -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut sigterm = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = signal::ctrl_c() => result?,
+            _ = sigterm.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    signal::ctrl_c().await?;
+    Ok(())
+}
 
-    let (commitment, payer, rpc_enpoint) = phoneix_config
-        .read_global_config()
-        .map_err(|e| Error::from(e.to_string()))?;
+/// Cancels the orders of every market the same way as `pomm cancle`, optionally withdrawing
+/// the free funds, and waits for the transactions to be confirmed.
+async fn cancel_all_quotes(
+    context: &PommContext,
+    markets: &[PhoenixOnChainMMConfig],
+    withdraw: bool,
+) -> anyhow::Result<()> {
+    let mut failed = vec![];
+    for market_config in markets {
+        if let Err(e) = cancel_quotes(context, market_config, withdraw).await {
+            println!("Failed to clean up {}: {:?}", market_config.ticker, e);
+            failed.push(market_config.ticker.to_string());
+        }
+    }
+    if !failed.is_empty() {
+        return Err(anyhow::anyhow!("Failed to clean up {}", failed.join(", ")));
+    }
+    Ok(())
+}
 
-    let client = RpcClient::new_with_commitment(rpc_enpoint.to_string(), commitment);
+async fn cancel_quotes(
+    context: &PommContext,
+    market_config: &PhoenixOnChainMMConfig,
+    withdraw: bool,
+) -> anyhow::Result<()> {
+    let market = market_config.market;
+    let cancel_order_tx_sig = context
+        .send_instructions(&[context.cancel_all_ix(market_config)])
+        .await?;
+    println!(
+        "Cancelled all orders of {}: {}",
+        market_config.ticker,
+        context.explorer_tx_url(&cancel_order_tx_sig)
+    );
 
-    let sdk = SDKClient::new(&payer, &rpc_enpoint)
-        .await
-        .map_err(|e| Error::from(e.to_string()))?;
+    if withdraw {
+        let header = context.get_market_header(&market).await?;
+        let (base_account, quote_account) = context.get_token_accounts(market_config).await?;
+        let ix = create_withdraw_funds_instruction_with_custom_token_accounts(
+            &market,
+            &context.payer.pubkey(),
+            &base_account,
+            &quote_account,
+            &header.base_params.mint_key,
+            &header.quote_params.mint_key,
+        );
+        let sig = context.send_instructions(&[ix]).await?;
+        println!(
+            "Withdrew the free funds of {}: {}",
+            market_config.ticker,
+            context.explorer_tx_url(&sig)
+        );
+    }
+    Ok(())
+}
 
-    let PhoenixOnChainMMConfig { market, .. } = phoneix_config.phoenix.clone();
+/// Waits for `delay`, returns true as soon as a shutdown is requested instead
+async fn wait_or_shutdown(shutdown: &mut watch::Receiver<bool>, delay: Duration) -> bool {
+    if *shutdown.borrow() {
+        return true;
+    }
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => false,
+        _ = shutdown.changed() => true,
+    }
+}
 
-    let base_account = phoneix_config
-        .phoenix
-        .get_base_oracle_account()
-        .map_err(|e| Error::from(e.to_string()))?;
-    let quote_account = phoneix_config
-        .phoenix
-        .get_quote_oracle_account()
-        .map_err(|e| Error::from(e.to_string()))?;
+/// Clients shared by the tasks of every market
+pub struct SharedClients {
+    pub context: PommContext,
+    pub blockhash_cache: BlockhashCache,
+}
 
-    // get price data from key
-    let mut base_price_account = client
-        .get_account(&base_account)
-        .await
-        .map_err(|e| Error::from(e.to_string()))?;
-    let base_price_feed = load_price_feed_from_account(&base_account, &mut base_price_account)
-        .map_err(|e| Error::from(e.to_string()))?;
+/// Reuses a recent blockhash across tasks instead of fetching one per transaction
+#[derive(Default)]
+pub struct BlockhashCache {
+    latest: Mutex<Option<(Hash, Instant)>>,
+}
 
-    let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+impl BlockhashCache {
+    pub async fn get(&self, client: &RpcClient) -> anyhow::Result<Hash> {
+        let mut latest = self.latest.lock().await;
+        match *latest {
+            Some((blockhash, fetched_at)) if fetched_at.elapsed() < BLOCKHASH_MAX_AGE => {
+                Ok(blockhash)
+            }
+            _ => {
+                let blockhash = client
+                    .get_latest_blockhash()
+                    .await
+                    .map_err(|e| Error::from(e.to_string()))?;
+                *latest = Some((blockhash, Instant::now()));
+                Ok(blockhash)
+            }
+        }
+    }
+}
 
-    let base_price = base_price_feed
-        .get_price_no_older_than(current_time, 60)
-        .ok_or(anyhow::anyhow!("base price is unavaiable"))?;
+/// Runs `task` until it returns `Ok`, restarting it when it fails or panics unless a shutdown
+/// was requested. The restart delay doubles up to `MAX_RESTART_DELAY` and resets once a run
+/// lasted that long.
+async fn supervise<F, Fut>(name: String, mut shutdown: watch::Receiver<bool>, task: F)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let mut delay = Duration::from_secs(1);
+    loop {
+        let started_at = Instant::now();
+        let mut run = AbortOnDrop(tokio::spawn(task()));
+        match (&mut run.0).await {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => println!("{} failed: {:?}", name, e),
+            Err(e) => println!("{} panicked: {:?}", name, e),
+        }
+        if started_at.elapsed() >= MAX_RESTART_DELAY {
+            delay = Duration::from_secs(1);
+        }
+        println!("Restarting {} in {:?}", name, delay);
+        if wait_or_shutdown(&mut shutdown, delay).await {
+            return;
+        }
+        delay = (delay * 2).min(MAX_RESTART_DELAY);
+    }
+}
 
-    let real_base_price = base_price.price as f64 * BASE.powi(base_price.expo);
+/// Aborts the task when dropped, so aborting `supervise` also stops the run in progress
+struct AbortOnDrop<T>(JoinHandle<T>);
 
-    println!(
-        "Base price ........... {} x 10^{} = {}",
-        base_price.price, base_price.expo, real_base_price
-    );
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
 
-    // get price data from key
-    let mut quote_price_account = client
-        .get_account(&quote_account)
-        .await
+async fn rebalance_task(
+    market_config: PhoenixOnChainMMConfig,
+    shared: Arc<SharedClients>,
+    reblance_sec: u64,
+    mut shutdown: watch::Receiver<bool>,
+) -> anyhow::Result<()> {
+    let context = &shared.context;
+    let client = context.client();
+
+    let market = market_config.market;
+
+    let base_account = context
+        .get_base_oracle_account(&market_config)
         .map_err(|e| Error::from(e.to_string()))?;
-    let quote_price_feed = load_price_feed_from_account(&quote_account, &mut quote_price_account)
+    let quote_account = context
+        .get_quote_oracle_account(&market_config)
         .map_err(|e| Error::from(e.to_string()))?;
 
-    let current_time = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| Error::from(e.to_string()))?
-        .as_secs() as i64;
+    let (base_token_account, quote_token_account) =
+        context.get_token_accounts(&market_config).await?;
+    let target_balance_ratio = 1.0;
 
-    let quote_price = quote_price_feed
-        .get_price_no_older_than(current_time, 60)
-        .ok_or(anyhow::anyhow!("base price is unavaiable"))?;
+    loop {
+        // Prices and balances move between rebalances, fetch them again every round
+        let mut base_price_account = client
+            .get_account(&base_account)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+        let base_price_feed = load_price_feed_from_account(&base_account, &mut base_price_account)
+            .map_err(|e| Error::from(e.to_string()))?;
 
-    let real_quote_price = quote_price.price as f64 * BASE.powi(quote_price.expo);
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
 
-    println!(
-        "Quote price ........... {} x 10^{} = {}",
-        quote_price.price, quote_price.expo, real_quote_price
-    );
+        let base_price = base_price_feed
+            .get_price_no_older_than(current_time, 60)
+            .ok_or(anyhow::anyhow!("base price is unavaiable"))?;
 
-    let data = client
-        .get_account_data(&phoneix_config.phoenix.market)
-        .await?;
-    let header =
-        bytemuck::try_from_bytes::<MarketHeader>(&data[..std::mem::size_of::<MarketHeader>()])
-            .map_err(|_| anyhow::Error::msg("Failed to parse Phoenix market header"))?;
+        let real_base_price = base_price.price as f64 * BASE.powi(base_price.expo);
 
-    let quote_token_account =
-        get_associated_token_address(&payer.pubkey(), &header.quote_params.mint_key);
-    let base_token_account =
-        get_associated_token_address(&payer.pubkey(), &header.base_params.mint_key);
+        println!(
+            "Base price ........... {} x 10^{} = {}",
+            base_price.price, base_price.expo, real_base_price
+        );
 
-    let quote_start_balance = client
-        .get_token_account_balance(&quote_token_account)
-        .await
-        .map_err(|e| Error::from(e.to_string()))?
-        .ui_amount_string;
+        // get price data from key
+        let mut quote_price_account = client
+            .get_account(&quote_account)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+        let quote_price_feed =
+            load_price_feed_from_account(&quote_account, &mut quote_price_account)
+                .map_err(|e| Error::from(e.to_string()))?;
 
-    let base_start_balance = client
-        .get_token_account_balance(&base_token_account)
-        .await
-        .map_err(|e| Error::from(e.to_string()))?
-        .ui_amount_string;
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| Error::from(e.to_string()))?
+            .as_secs() as i64;
 
-    println!(
-        "Base Balance: {:#?}, QuoteBalance: {:#?}\n",
-        base_start_balance, quote_start_balance
-    );
+        let quote_price = quote_price_feed
+            .get_price_no_older_than(current_time, 60)
+            .ok_or(anyhow::anyhow!("base price is unavaiable"))?;
+
+        let real_quote_price = quote_price.price as f64 * BASE.powi(quote_price.expo);
+
+        println!(
+            "Quote price ........... {} x 10^{} = {}",
+            quote_price.price, quote_price.expo, real_quote_price
+        );
+
+        let quote_token_balance = client
+            .get_token_account_balance(&quote_token_account)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?
+            .ui_amount_string;
+
+        let base_token_balance = client
+            .get_token_account_balance(&base_token_account)
+            .await
+            .map_err(|e| Error::from(e.to_string()))?
+            .ui_amount_string;
+
+        println!(
+            "Base Balance: {:#?}, QuoteBalance: {:#?}\n",
+            base_token_balance, quote_token_balance
+        );
+
+        let base_balance = real_base_price
+            * base_token_balance
+                .parse::<f64>()
+                .map_err(|e| Error::from(e.to_string()))?;
+        let quote_balance = real_quote_price
+            * quote_token_balance
+                .parse::<f64>()
+                .map_err(|e| Error::from(e.to_string()))?;
 
-    let base_balance = real_base_price
-        * base_start_balance
-            .parse::<f64>()
-            .map_err(|e| Error::from(e.to_string()))?;
-    let quote_balance = real_quote_price
-        * quote_start_balance
-            .parse::<f64>()
-            .map_err(|e| Error::from(e.to_string()))?;
-    let target_balance_ratio = 1.0;
-    loop {
         rebalance(
             &market,
             base_balance,
@@ -163,11 +382,13 @@ async fn rebalance_task(reblance_sec: u64) -> anyhow::Result<()> {
             quote_balance,
             real_quote_price,
             target_balance_ratio,
-            &sdk,
+            context,
         )
         .await?;
 
-        tokio::time::sleep(std::time::Duration::from_secs(reblance_sec)).await;
+        if wait_or_shutdown(&mut shutdown, Duration::from_secs(reblance_sec)).await {
+            return Ok(());
+        }
     }
 }
 
@@ -178,8 +399,9 @@ async fn rebalance(
     quote_balance: f64,
     quote_price: f64,
     target_balance_ratio: f64,
-    sdk: &SDKClient,
+    context: &PommContext,
 ) -> anyhow::Result<()> {
+    let sdk = &context.sdk;
     let current_ratio = base_balance / quote_balance;
 
     if current_ratio > target_balance_ratio + 0.05 {
@@ -196,10 +418,7 @@ async fn rebalance(
             .await
             .ok_or(anyhow::anyhow!("send ioc retuen error"))?;
 
-        println!(
-            "Rebalance Base Coin : https://explorer.solana.com/tx/{}?cluster=devnet",
-            sig
-        );
+        println!("Rebalance Base Coin : {}", context.explorer_tx_url(&sig));
     } else if current_ratio < target_balance_ratio - 0.05 {
         let balance_to_convert = (target_balance_ratio - current_ratio) * quote_balance;
         let quote_size = (base_balance / quote_price) as u64;
@@ -213,49 +432,21 @@ async fn rebalance(
             )
             .await
             .ok_or(anyhow::anyhow!("send ioc retuen error"))?;
-        println!(
-            "Rebalance Quote Coin : https://explorer.solana.com/tx/{}?cluster=devnet",
-            sig
-        );
+        println!("Rebalance Quote Coin : {}", context.explorer_tx_url(&sig));
     }
 
     Ok(())
 }
 
-async fn update_quote() -> anyhow::Result<()> {
-    let phoneix_config = get_pomm_config().map_err(|e| Error::from(e.to_string()))?;
-
-    let (commitment, payer, rpc_enpoint) = phoneix_config
-        .read_global_config()
-        .map_err(|e| Error::from(e.to_string()))?;
-
-    let client = RpcClient::new_with_commitment(rpc_enpoint.to_string(), commitment);
-
-    let mut sdk = phoenix_sdk::sdk_client::SDKClient::new(&payer, &rpc_enpoint)
-        .await
-        .map_err(|e| Error::from(e.to_string()))?;
-
-    let PhoenixOnChainMMConfig {
-        market,
-        ticker: _,
-        quote_edge_in_bps,
-        quote_size,
-        quote_refresh_frequency_in_ms,
-        price_improvement_behavior,
-        post_only,
-    } = phoneix_config.phoenix.clone();
-
-    let oracle_base_account = phoneix_config
-        .phoenix
-        .get_base_oracle_account()
-        .map_err(|e| Error::from(e.to_string()))?;
-    let oracle_quote_account = phoneix_config
-        .phoenix
-        .get_quote_oracle_account()
-        .map_err(|e| Error::from(e.to_string()))?;
-
-    // add market pubkey to sdk
-    sdk.add_market(&market).await?;
+async fn update_quote(
+    market_config: PhoenixOnChainMMConfig,
+    shared: Arc<SharedClients>,
+    mut shutdown: watch::Receiver<bool>,
+) -> anyhow::Result<()> {
+    let context = &shared.context;
+    let client = context.client();
+    let sdk = &context.sdk;
+    let market = market_config.market;
 
     let maker_setup_instructions = sdk
         .get_maker_setup_instructions_for_market(&market)
@@ -268,119 +459,45 @@ async fn update_quote() -> anyhow::Result<()> {
         .await
         .map_err(|e| Error::from(e.to_string()))?;
 
-    println!(
-        "Claim maker seta: https://explorer.solana.com/tx/{}?cluster=devnet",
-        ix
-    );
-
-    let (strategy_key, _bump_seed) = Pubkey::find_program_address(
-        &[
-            PHOENIX_ONCHAIN_MM_STRATEGY_SEED,
-            payer.pubkey().as_ref(),
-            market.as_ref(),
-        ],
-        &ids::phoenix_onchain_mm_program::id(),
-    );
-
-    let (oracle_account, _) = Pubkey::find_program_address(
-        &[
-            PHOENIX_ONCHAIN_MM_ORACLE_SEED,
-            payer.pubkey().as_ref(),
-            market.as_ref(),
-        ],
-        &ids::phoenix_onchain_mm_program::id(),
-    );
+    println!("Claim maker seta: {}", context.explorer_tx_url(&ix));
 
-    let price_improvement = match price_improvement_behavior.as_str() {
-        "Join" | "join" => PriceImprovementBehavior::Join,
-        "Dime" | "dime" => PriceImprovementBehavior::Dime,
-        "Ignore" | "ignore" => PriceImprovementBehavior::Ignore,
-        _ => PriceImprovementBehavior::Join,
-    };
-
-    let params = StrategyParams {
-        quote_edge_in_bps: Some(quote_edge_in_bps),
-        quote_size_in_quote_atoms: Some(quote_size),
-        price_improvement_behavior: Some(price_improvement),
-        post_only: Some(post_only),
-        oracle_account_config: OracleConfig {
-            oracle_base_account,
-            oracle_quote_account,
-        },
-    };
-
-    let data = client
-        .get_account_data(&market)
+    let params = context
+        .get_strategy_params(&market_config)
         .await
         .map_err(|e| Error::from(e.to_string()))?;
-    let header =
-        bytemuck::try_from_bytes::<MarketHeader>(&data[..std::mem::size_of::<MarketHeader>()])
-            .map_err(|_| anyhow::Error::msg("Failed to parse Phoenix market header"))?;
 
     println!("Quote Params: {:#?}", params);
 
-    loop {
-        let args = UpdateQuotesInstruction {
-            params: OrderParams {
-                strategy_params: params,
-            },
-        };
-
-        let quote_account =
-            get_associated_token_address(&payer.pubkey(), &header.quote_params.mint_key);
-
-        let base_account =
-            get_associated_token_address(&payer.pubkey(), &header.base_params.mint_key);
-
-        let accounts = UpdateQuotesAccounts {
-            phoenix_strategy: strategy_key,
-            oracle_account,
-            oracle_base_price: oracle_base_account,
-            oracle_quote_price: oracle_quote_account,
-            market,
-            user: payer.pubkey(),
-            phoenix_program: phoenix::id(),
-            log_authority: phoenix::phoenix_log_authority::id(),
-            seat: get_seat_address(&market, &payer.pubkey()).0,
-            quote_account,
-            base_account,
-            quote_vault: get_vault_address(&market, &header.quote_params.mint_key).0,
-            base_vault: get_vault_address(&market, &header.base_params.mint_key).0,
-            token_program: spl_token::id(),
-        };
-
-        let ix = Instruction {
-            program_id: ids::phoenix_onchain_mm_program::id(),
-            accounts: accounts.to_account_metas(None),
-            data: args.data(),
-        };
+    let ix = context
+        .update_quotes_ix(&market_config, params)
+        .await
+        .map_err(|e| Error::from(e.to_string()))?;
 
+    loop {
         let transaction = Transaction::new_signed_with_payer(
-            &[ix],
-            Some(&payer.pubkey()),
-            &[&payer],
-            client
-                .get_latest_blockhash()
-                .await
-                .map_err(|e| Error::from(e.to_string()))?,
+            std::slice::from_ref(&ix),
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            shared.blockhash_cache.get(client).await?,
         );
 
         match client
             .send_and_confirm_transaction(&transaction)
             .await
             .map(|sig| {
-                println!(
-                    "Updating quotes: https://explorer.solana.com/tx/{}?cluster=devnet",
-                    sig
-                );
+                println!("Updating quotes: {}", context.explorer_tx_url(&sig));
             }) {
             Ok(_) => {}
             Err(e) => println!("Failed to update quotes: {:#?}", e),
         }
 
-        tokio::time::sleep(std::time::Duration::from_millis(
-            quote_refresh_frequency_in_ms,
-        ))
-        .await;
+        if wait_or_shutdown(
+            &mut shutdown,
+            Duration::from_millis(market_config.quote_refresh_frequency_in_ms),
+        )
+        .await
+        {
+            return Ok(());
+        }
     }
 }
@@ -1,9 +1,9 @@
+use crate::amount::{format_base_lots, format_price_in_ticks};
 use crate::config::PhoenixOnChainMMConfig;
+use crate::context::PommContext;
 use crate::errors::Error;
-use crate::utils::get_pomm_config;
-use ellipsis_client::EllipsisClient;
-use phoenix_sdk::sdk_client::SDKClient;
-use solana_client::nonblocking::rpc_client::RpcClient;
+use crate::utils::ConfigSelection;
+use phoenix_onchain_mm::state::PhoenixStrategyState;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -15,28 +15,50 @@ pub struct ViewStateOrderBook {
 }
 
 impl ViewStateOrderBook {
-    pub async fn run(&self) -> anyhow::Result<()> {
-        let phoneix_config = get_pomm_config().map_err(|e| Error::from(e.to_string()))?;
-
-        let (commitment, payer, rpc_enpoint) = phoneix_config
-            .read_global_config()
+    pub async fn run(&self, selection: &ConfigSelection) -> anyhow::Result<()> {
+        let context = PommContext::load(selection)
+            .await
             .map_err(|e| Error::from(e.to_string()))?;
+        let PhoenixOnChainMMConfig { market, ticker, .. } = context.market_config();
+        let strategy_key = context.get_strategy_address(context.market_config());
 
-        let PhoenixOnChainMMConfig { market, .. } = phoneix_config.phoenix;
-
-        let client = EllipsisClient::from_rpc(
-            RpcClient::new_with_commitment(rpc_enpoint, commitment),
-            &payer,
-        )?;
-        let sdk_client = SDKClient::new_from_ellipsis_client(client)
+        let orderbook = context
+            .sdk
+            .get_market_orderbook(market)
             .await
             .map_err(|e| Error::from(e.to_string()))?;
+        orderbook.print_ladder(self.levels, self.precision);
 
-        let orderbook = sdk_client
-            .get_market_orderbook(&market)
+        let header = context.get_market_header(market).await?;
+        let data = context
+            .client()
+            .get_account_data(&strategy_key)
             .await
             .map_err(|e| Error::from(e.to_string()))?;
-        orderbook.print_ladder(self.levels, self.precision);
+        // Skip the 8 byte account discriminator
+        let state = bytemuck::try_from_bytes::<PhoenixStrategyState>(
+            &data[8..8 + std::mem::size_of::<PhoenixStrategyState>()],
+        )
+        .map_err(|_| anyhow::Error::msg("Failed to parse strategy state"))?;
+        let (bid_price, bid_size) = (
+            state.bid_price_in_ticks,
+            state.initial_bid_size_in_base_lots,
+        );
+        let (ask_price, ask_size) = (
+            state.ask_price_in_ticks,
+            state.initial_ask_size_in_base_lots,
+        );
+
+        println!(
+            "\nOur bid .............. {} @ {}",
+            format_base_lots(bid_size, &header, ticker),
+            format_price_in_ticks(bid_price, &header, ticker)
+        );
+        println!(
+            "Our ask .............. {} @ {}",
+            format_base_lots(ask_size, &header, ticker),
+            format_price_in_ticks(ask_price, &header, ticker)
+        );
 
         Ok(())
     }
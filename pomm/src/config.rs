@@ -1,17 +1,127 @@
+use crate::amount::Amount;
+use crate::cluster::Cluster;
 use crate::ids;
+use crate::oracle::OracleRegistry;
 use anyhow::anyhow;
-use serde::{Deserialize, Deserializer};
+use phoenix::program::MarketHeader;
+use phoenix_onchain_mm::client::get_strategy_address_with_program_id;
+use phoenix_onchain_mm::constant::MAX_SCHEDULE_PROFILES;
+use phoenix_onchain_mm::phoenix_v1::get_market_params;
+use phoenix_onchain_mm::price::{ask_price_in_ticks, size_in_base_lots};
+use phoenix_onchain_mm::{
+    FairPriceMode, PriceImprovementBehavior, QuoteModel, ScheduleProfileParams, SelfTradeBehavior,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use solana_cli_config::{Config as SolanaConfig, ConfigInput, CONFIG_FILE};
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::read_keypair_file;
 use solana_sdk::signature::Keypair;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::str::FromStr;
 
+/// Prefix of the environment variables overriding config values
+const ENV_PREFIX: &str = "POMM_";
+
+/// Where a resolved config value comes from, in increasing order of precedence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    SolanaCli,
+    File,
+    Profile(String),
+    Env(String),
+    Cli,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::SolanaCli => write!(f, "solana cli config"),
+            ConfigSource::File => write!(f, "config file"),
+            ConfigSource::Profile(name) => write!(f, "profile {}", name),
+            ConfigSource::Env(name) => write!(f, "env {}", name),
+            ConfigSource::Cli => write!(f, "--set"),
+        }
+    }
+}
+
+/// A value set outside the config file for a dotted key such as `phoenix.quote_edge_in_bps`.
+#[derive(Debug, Clone)]
+pub struct ConfigOverride {
+    pub key: String,
+    pub value: toml::Value,
+    pub source: ConfigSource,
+}
+
+impl ConfigOverride {
+    /// Parses a `key=value` pair given to `--set`.
+    pub fn from_cli(arg: &str) -> anyhow::Result<Self> {
+        let (key, value) = arg
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid --set {}, expected key=value", arg))?;
+        Ok(ConfigOverride {
+            key: key.trim().to_string(),
+            value: parse_override_value(value.trim()),
+            source: ConfigSource::Cli,
+        })
+    }
+
+    /// Collects the `POMM_*` variables, `POMM_RPC_ENDPOINT` sets `rpc_endpoint`,
+    /// `POMM_PHOENIX_QUOTE_EDGE_IN_BPS` sets `phoenix.quote_edge_in_bps`,
+    /// `POMM_MARKETS_0_QUOTE_EDGE_IN_BPS` sets `markets.0.quote_edge_in_bps` and
+    /// `POMM_PROGRAM_IDS_MAINNET_BETA` sets `program_ids.mainnet-beta`. `POMM_CONFIG` and
+    /// `POMM_PROFILE` choose the config file and profile instead.
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Vec<Self> {
+        let mut overrides = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase();
+                if key.is_empty() || key == "config" || key == "profile" {
+                    return None;
+                }
+                let key = if let Some(field) = key.strip_prefix("phoenix_") {
+                    format!("phoenix.{}", field)
+                } else if let Some(cluster) = key.strip_prefix("program_ids_") {
+                    // Cluster names are kebab-case, e.g. mainnet-beta
+                    format!("program_ids.{}", cluster.replace('_', "-"))
+                } else if let Some((index, field)) = key
+                    .strip_prefix("markets_")
+                    .and_then(|rest| rest.split_once('_'))
+                    .filter(|(index, _)| index.parse::<usize>().is_ok())
+                {
+                    format!("markets.{}.{}", index, field)
+                } else {
+                    key
+                };
+                Some(ConfigOverride {
+                    key,
+                    value: parse_override_value(&value),
+                    source: ConfigSource::Env(name),
+                })
+            })
+            .collect::<Vec<_>>();
+        overrides.sort_by(|a, b| a.key.cmp(&b.key));
+        overrides
+    }
+}
+
+/// Reads an override as a TOML value so numbers and booleans keep their type, anything
+/// that is not valid TOML is taken as a plain string.
+fn parse_override_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
 /// This is what we're going to decode into. Each field is optional, meaning
 /// that it doesn't have to be present in TOML.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     /// Optionally include your keypair path. Defaults to your Solana CLI config file.
     pub keypair_path: Option<String>,
@@ -19,28 +129,248 @@ pub struct Config {
     pub rpc_endpoint: Option<String>,
     /// Optionally include a commitment level. Defaults to your Solana CLI config file.
     pub commitment: Option<String>,
+    /// Program id per cluster, e.g. `devnet = "..."` under `[program_ids]`. Defaults to the id the program is built with.
+    #[serde(
+        default,
+        deserialize_with = "parse_program_ids",
+        serialize_with = "serialize_program_ids"
+    )]
+    pub program_ids: BTreeMap<Cluster, Pubkey>,
+    /// Transaction link template with a `{signature}` placeholder, e.g. for a local explorer.
+    /// Defaults to explorer.solana.com for the cluster of the RPC endpoint.
+    pub explorer_url: Option<String>,
     pub phoenix: PhoenixOnChainMMConfig,
+    /// Markets quoted together by `pomm update-quotes`, each inheriting the `[phoenix]` keys it does not set.
+    #[serde(default)]
+    pub markets: Vec<PhoenixOnChainMMConfig>,
+    /// Source of every value set by the config file or an override, keyed by dotted path.
+    #[serde(skip)]
+    pub sources: BTreeMap<String, ConfigSource>,
 }
 
 impl Config {
-    pub fn read_global_config(&self) -> anyhow::Result<(CommitmentConfig, Keypair, String)> {
-        let (commitment, keypair_path, rpc_enpoint) =
-            if let (Some(commitment), Some(keypair_path), Some(rpc_endpoint)) = (
-                self.commitment.clone(),
-                self.keypair_path.clone(),
-                self.rpc_endpoint.clone(),
-            ) {
-                (commitment, keypair_path, rpc_endpoint)
-            } else {
-                let config = match CONFIG_FILE.as_ref() {
-                    Some(config_file) => SolanaConfig::load(config_file).unwrap_or_else(|_| {
-                        println!("Failed to load config file: {}", config_file);
-                        SolanaConfig::default()
-                    }),
-                    None => SolanaConfig::default(),
-                };
-                (config.commitment, config.keypair_path, config.json_rpc_url)
+    /// Parses a config file. Files with a `[profiles.<name>]` table pick the profile named by
+    /// `profile`, then by `default_profile`, then the only profile defined. Keys of the profile,
+    /// including those in its `[profiles.<name>.phoenix]` table, override the top-level ones,
+    /// so settings shared by every profile can stay at the top.
+    pub fn from_toml_str(config_str: &str, profile: Option<&str>) -> anyhow::Result<Self> {
+        Self::from_layers(config_str, profile, &[])
+    }
+
+    /// Parses a config file like `from_toml_str`, then applies `overrides` in order on top of
+    /// the selected profile. `[[markets]]` entries inherit overridden `[phoenix]` keys they do not set.
+    pub fn from_layers(
+        config_str: &str,
+        profile: Option<&str>,
+        overrides: &[ConfigOverride],
+    ) -> anyhow::Result<Self> {
+        let mut table: toml::Table = toml::from_str(config_str)?;
+        let default_profile = match table.remove("default_profile") {
+            Some(toml::Value::String(name)) => Some(name),
+            Some(value) => {
+                return Err(anyhow!("default_profile should be a string, got {}", value))
+            }
+            None => None,
+        };
+        let profiles = match table.remove("profiles") {
+            Some(toml::Value::Table(profiles)) => profiles,
+            Some(value) => return Err(anyhow!("profiles should be a table, got {}", value)),
+            None => toml::Table::new(),
+        };
+
+        let name = match profile.map(str::to_string).or(default_profile) {
+            Some(name) => Some(name),
+            None if profiles.len() == 1 && !table.contains_key("phoenix") => {
+                profiles.keys().next().cloned()
+            }
+            None if !profiles.is_empty() && !table.contains_key("phoenix") => {
+                return Err(anyhow!(
+                    "Choose a profile with --profile, available: {}",
+                    profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                ))
+            }
+            None => None,
+        };
+        let mut sources = BTreeMap::new();
+        record_sources(&mut sources, "", &table, &ConfigSource::File);
+        if let Some(name) = name {
+            match profiles.get(&name) {
+                Some(toml::Value::Table(overlay)) => {
+                    record_sources(
+                        &mut sources,
+                        "",
+                        overlay,
+                        &ConfigSource::Profile(name.clone()),
+                    );
+                    merge_tables(&mut table, overlay.clone())
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "Profile {} not found, available: {}",
+                        name,
+                        profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                    ))
+                }
+            }
+        }
+        for config_override in overrides {
+            set_dotted_key(
+                &mut table,
+                &config_override.key,
+                config_override.value.clone(),
+            )?;
+            sources.insert(config_override.key.clone(), config_override.source.clone());
+        }
+        // Every [[markets]] entry inherits the keys of [phoenix] it does not set, and the
+        // first one stands in for [phoenix] in the single-market commands when it is missing
+        if let Some(markets) = table.remove("markets") {
+            let markets = match markets {
+                toml::Value::Array(markets) => markets,
+                value => return Err(anyhow!("markets should be an array, got {}", value)),
+            };
+            let base = match table.get("phoenix") {
+                Some(toml::Value::Table(phoenix)) => phoenix.clone(),
+                _ => toml::Table::new(),
             };
+            let markets = markets
+                .into_iter()
+                .map(|market| match market {
+                    toml::Value::Table(overlay) => {
+                        let mut merged = base.clone();
+                        merge_tables(&mut merged, overlay);
+                        Ok(toml::Value::Table(merged))
+                    }
+                    value => Err(anyhow!("markets entries should be tables, got {}", value)),
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            if !table.contains_key("phoenix") {
+                if let Some(first) = markets.first() {
+                    table.insert("phoenix".to_string(), first.clone());
+                    let first_sources = sources
+                        .iter()
+                        .filter_map(|(key, source)| {
+                            let field = key.strip_prefix("markets.0.")?;
+                            Some((format!("phoenix.{}", field), source.clone()))
+                        })
+                        .collect::<Vec<_>>();
+                    sources.extend(first_sources);
+                }
+            }
+            table.insert("markets".to_string(), toml::Value::Array(markets));
+        }
+        // Deserialize from text, so errors carry a span that leads back to the key and its origin
+        let merged = toml::to_string(&table)?;
+        let mut config: Config = toml::from_str(&merged).map_err(|e| {
+            let mut key = e
+                .span()
+                .and_then(|span| find_key_at(&merged, span.start))
+                .unwrap_or_default();
+            // Unknown fields are reported on their table, point at the field itself
+            if let Some(field) = e
+                .message()
+                .strip_prefix("unknown field `")
+                .and_then(|rest| rest.split_once('`'))
+                .map(|(field, _)| field)
+            {
+                key = match key.is_empty() {
+                    true => field.to_string(),
+                    false => format!("{}.{}", key, field),
+                };
+            }
+            anyhow!(
+                "Invalid {}{}: {}",
+                key,
+                describe_origin(&key, &sources, config_str),
+                e.message()
+            )
+        })?;
+        config.sources = sources;
+        for (prefix, market) in std::iter::once(("phoenix".to_string(), &config.phoenix)).chain(
+            config
+                .markets
+                .iter()
+                .enumerate()
+                .map(|(index, market)| (format!("markets.{}", index), market)),
+        ) {
+            let checks = [
+                ("quote_model", market.get_quote_model().err()),
+                (
+                    "schedule",
+                    (market.schedule.len() > MAX_SCHEDULE_PROFILES).then(|| {
+                        anyhow!(
+                            "At most {} schedule profiles are supported, got {}",
+                            MAX_SCHEDULE_PROFILES,
+                            market.schedule.len()
+                        )
+                    }),
+                ),
+            ];
+            for (field, error) in checks {
+                if let Some(error) = error {
+                    let key = format!("{}.{}", prefix, field);
+                    return Err(anyhow!(
+                        "Invalid {}{}: {}",
+                        key,
+                        describe_origin(&key, &config.sources, config_str),
+                        error
+                    ));
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Returns where the value at the dotted `key` comes from. Market keys not set by the
+    /// market itself come from `[phoenix]`.
+    pub fn get_source(&self, key: &str) -> ConfigSource {
+        get_origin(&self.sources, key).map_or(ConfigSource::Default, |(_, source)| source)
+    }
+
+    /// Returns the `[[markets]]` entries, or the `[phoenix]` section when there are none.
+    pub fn get_markets(&self) -> Vec<PhoenixOnChainMMConfig> {
+        if self.markets.is_empty() {
+            vec![self.phoenix.clone()]
+        } else {
+            self.markets.clone()
+        }
+    }
+
+    /// Returns the commitment, keypair path and RPC endpoint. Each one that is not configured
+    /// falls back to the Solana CLI config on its own.
+    pub fn resolve_global_config(&self) -> (String, String, String) {
+        let mut solana_config = None;
+        let mut from_solana_config = |field: fn(SolanaConfig) -> String| {
+            field(
+                solana_config
+                    .get_or_insert_with(load_solana_cli_config)
+                    .clone(),
+            )
+        };
+        let commitment = self
+            .commitment
+            .clone()
+            .unwrap_or_else(|| from_solana_config(|config| config.commitment));
+        let keypair_path = self
+            .keypair_path
+            .clone()
+            .unwrap_or_else(|| from_solana_config(|config| config.keypair_path));
+        let rpc_endpoint = self
+            .rpc_endpoint
+            .clone()
+            .unwrap_or_else(|| from_solana_config(|config| config.json_rpc_url));
+        (commitment, keypair_path, rpc_endpoint)
+    }
+
+    /// Returns the program id configured for `cluster`, or the id the program is built with.
+    pub fn get_program_id(&self, cluster: Cluster) -> Pubkey {
+        self.program_ids
+            .get(&cluster)
+            .copied()
+            .unwrap_or_else(ids::phoenix_onchain_mm_program::id)
+    }
+
+    pub fn read_global_config(&self) -> anyhow::Result<(CommitmentConfig, Keypair, String)> {
+        let (commitment, keypair_path, rpc_enpoint) = self.resolve_global_config();
         let commitment = ConfigInput::compute_commitment_config("", &commitment).1;
         let payer = get_payer_keypair_from_path(&keypair_path)?;
         let network_url = get_network(&rpc_enpoint).to_string();
@@ -48,6 +378,208 @@ impl Config {
     }
 }
 
+/// Returns the key a value was set at and its source, following market keys the market
+/// does not set back to `[phoenix]`.
+fn get_origin(
+    sources: &BTreeMap<String, ConfigSource>,
+    key: &str,
+) -> Option<(String, ConfigSource)> {
+    if let Some(source) = sources.get(key) {
+        return Some((key.to_string(), source.clone()));
+    }
+    let (_, field) = key.strip_prefix("markets.")?.split_once('.')?;
+    let key = format!("phoenix.{}", field);
+    let source = sources.get(&key)?.clone();
+    Some((key, source))
+}
+
+/// Describes where the value at `key` was set, e.g. " at line 12, column 30" for the config file.
+fn describe_origin(
+    key: &str,
+    sources: &BTreeMap<String, ConfigSource>,
+    config_str: &str,
+) -> String {
+    let position = |key: &str| {
+        let document = toml_edit::ImDocument::parse(config_str).ok()?;
+        let span = key
+            .split('.')
+            .try_fold(document.as_item(), |item, segment| {
+                match segment.parse::<usize>() {
+                    Ok(index) => item.get(index),
+                    Err(_) => item.get(segment),
+                }
+            })?
+            .span()?;
+        let before = &config_str[..span.start];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        Some(format!(
+            " at line {}, column {}",
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1
+        ))
+    };
+    match get_origin(sources, key) {
+        Some((key, ConfigSource::File)) => position(&key).unwrap_or_default(),
+        Some((key, ConfigSource::Profile(name))) => {
+            position(&format!("profiles.{}.{}", name, key)).unwrap_or_default()
+        }
+        Some((_, ConfigSource::Env(name))) => format!(" set by {}", name),
+        Some((_, ConfigSource::Cli)) => " set by --set".to_string(),
+        // Tables and missing keys have no source of their own
+        _ => position(key).unwrap_or_default(),
+    }
+}
+
+/// Returns the dotted key of the deepest value or table of `document` containing `offset`.
+fn find_key_at(document: &str, offset: usize) -> Option<String> {
+    fn find_in_table(table: &toml_edit::Table, prefix: &str, offset: usize) -> Option<String> {
+        let contains =
+            |span: Option<std::ops::Range<usize>>| span.is_some_and(|span| span.contains(&offset));
+        table.iter().find_map(|(key, item)| {
+            let key = format!("{}{}", prefix, key);
+            let nested = match item {
+                toml_edit::Item::Table(table) => find_in_table(table, &format!("{}.", key), offset),
+                toml_edit::Item::ArrayOfTables(array) => {
+                    array.iter().enumerate().find_map(|(index, table)| {
+                        find_in_table(table, &format!("{}.{}.", key, index), offset).or_else(|| {
+                            contains(table.span()).then(|| format!("{}.{}", key, index))
+                        })
+                    })
+                }
+                _ => None,
+            };
+            nested.or_else(|| contains(item.span()).then_some(key))
+        })
+    }
+    let document = toml_edit::ImDocument::parse(document).ok()?;
+    find_in_table(document.as_table(), "", offset)
+}
+
+fn load_solana_cli_config() -> SolanaConfig {
+    match CONFIG_FILE.as_ref() {
+        Some(config_file) => SolanaConfig::load(config_file).unwrap_or_else(|_| {
+            println!("Failed to load config file: {}", config_file);
+            SolanaConfig::default()
+        }),
+        None => SolanaConfig::default(),
+    }
+}
+
+/// Records `source` for every value of `table`, under dotted keys starting with `prefix`.
+/// Entries of arrays of tables are keyed by their index, e.g. `markets.0.ticker`.
+fn record_sources(
+    sources: &mut BTreeMap<String, ConfigSource>,
+    prefix: &str,
+    table: &toml::Table,
+    source: &ConfigSource,
+) {
+    for (key, value) in table {
+        let key = format!("{}{}", prefix, key);
+        match value {
+            toml::Value::Table(table) => {
+                record_sources(sources, &format!("{}.", key), table, source)
+            }
+            toml::Value::Array(array) if array.iter().all(toml::Value::is_table) => {
+                for (index, value) in array.iter().enumerate() {
+                    if let toml::Value::Table(table) = value {
+                        record_sources(sources, &format!("{}.{}.", key, index), table, source)
+                    }
+                }
+            }
+            _ => {
+                sources.insert(key, source.clone());
+            }
+        }
+    }
+}
+
+/// Sets the value at a dotted key such as `phoenix.quote_edge_in_bps` or
+/// `markets.0.quote_edge_in_bps`, creating missing tables. A numeric segment indexes an array,
+/// the index right past its end appends an entry.
+fn set_dotted_key(table: &mut toml::Table, key: &str, value: toml::Value) -> anyhow::Result<()> {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let child = table
+                .entry(head.to_string())
+                .or_insert_with(|| empty_container(rest));
+            set_nested_key(child, key, head, rest, value)
+        }
+        None if key.is_empty() => Err(anyhow!("Empty config key")),
+        None => {
+            table.insert(key.to_string(), value);
+            Ok(())
+        }
+    }
+}
+
+/// Sets `rest` of `key` inside `node`, the table or array found at segment `head`.
+fn set_nested_key(
+    node: &mut toml::Value,
+    key: &str,
+    head: &str,
+    rest: &str,
+    value: toml::Value,
+) -> anyhow::Result<()> {
+    match node {
+        toml::Value::Table(table) => set_dotted_key(table, rest, value),
+        toml::Value::Array(array) => {
+            let (index, tail) = match rest.split_once('.') {
+                Some((index, tail)) => (index, Some(tail)),
+                None => (rest, None),
+            };
+            let index = index
+                .parse::<usize>()
+                .ok()
+                .filter(|index| *index <= array.len())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Can't set {}, expected an index of {} up to {}",
+                        key,
+                        head,
+                        array.len()
+                    )
+                })?;
+            match tail {
+                None if index == array.len() => array.push(value),
+                None => array[index] = value,
+                Some(tail) => {
+                    if index == array.len() {
+                        array.push(empty_container(tail));
+                    }
+                    let path = format!("{}.{}", head, index);
+                    return set_nested_key(&mut array[index], key, &path, tail, value);
+                }
+            }
+            Ok(())
+        }
+        _ => Err(anyhow!("Can't set {}, {} is not a table", key, head)),
+    }
+}
+
+/// Returns the array or table a dotted key continuing with `rest` needs, depending on whether
+/// its next segment is an index.
+fn empty_container(rest: &str) -> toml::Value {
+    let segment = rest.split('.').next().unwrap_or_default();
+    match segment.parse::<usize>() {
+        Ok(_) => toml::Value::Array(Vec::new()),
+        Err(_) => toml::Value::Table(toml::Table::new()),
+    }
+}
+
+/// Recursively overrides the keys of `base` with those of `overlay`.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 fn parse_pubkey<'de, D>(deserializer: D) -> Result<Pubkey, D::Error>
 where
     D: Deserializer<'de>,
@@ -56,49 +588,559 @@ where
     Pubkey::from_str(&pubkey_str).map_err(serde::de::Error::custom)
 }
 
+fn serialize_pubkey<S>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&pubkey.to_string())
+}
+
+fn serialize_optional_pubkey<S>(pubkey: &Option<Pubkey>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match pubkey {
+        Some(pubkey) => serialize_pubkey(pubkey, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn parse_program_ids<'de, D>(deserializer: D) -> Result<BTreeMap<Cluster, Pubkey>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    BTreeMap::<Cluster, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(cluster, program_id)| {
+            Pubkey::from_str(&program_id)
+                .map(|program_id| (cluster, program_id))
+                .map_err(serde::de::Error::custom)
+        })
+        .collect()
+}
+
+fn serialize_program_ids<S>(
+    program_ids: &BTreeMap<Cluster, Pubkey>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_map(
+        program_ids
+            .iter()
+            .map(|(cluster, program_id)| (cluster, program_id.to_string())),
+    )
+}
+
+fn parse_optional_pubkey<'de, D>(deserializer: D) -> Result<Option<Pubkey>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    parse_pubkey(deserializer).map(Some)
+}
+
 /// Sub-structs are decoded from tables, so this will decode from the `[server]`
 /// table.
 ///
 /// Again, each field is optional, meaning they don't have to be present.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct PhoenixOnChainMMConfig {
     /// Market pubkey to provide on
-    #[serde(deserialize_with = "parse_pubkey")]
+    #[serde(deserialize_with = "parse_pubkey", serialize_with = "serialize_pubkey")]
     pub market: Pubkey,
+    /// Strategy account to operate on. Only needed once the trader or admin key differs from the key that
+    /// initialized the strategy, otherwise it is derived from the keypair and the market.
+    #[serde(
+        default,
+        deserialize_with = "parse_optional_pubkey",
+        serialize_with = "serialize_optional_pubkey"
+    )]
+    pub strategy: Option<Pubkey>,
+    /// Token account the base token is traded from, e.g. a treasury account that is not an
+    /// associated token account. Defaults to the associated token account of the keypair.
+    #[serde(
+        default,
+        deserialize_with = "parse_optional_pubkey",
+        serialize_with = "serialize_optional_pubkey"
+    )]
+    pub base_token_account: Option<Pubkey>,
+    /// Token account the quote token is traded from. Defaults to the associated token account of the keypair.
+    #[serde(
+        default,
+        deserialize_with = "parse_optional_pubkey",
+        serialize_with = "serialize_optional_pubkey"
+    )]
+    pub quote_token_account: Option<Pubkey>,
+    /// Base price oracle account. Defaults to the oracle registry entry for the base symbol of `ticker`.
+    #[serde(
+        default,
+        deserialize_with = "parse_optional_pubkey",
+        serialize_with = "serialize_optional_pubkey"
+    )]
+    pub base_oracle: Option<Pubkey>,
+    /// Quote price oracle account. Defaults to the oracle registry entry for the quote symbol of `ticker`.
+    #[serde(
+        default,
+        deserialize_with = "parse_optional_pubkey",
+        serialize_with = "serialize_optional_pubkey"
+    )]
+    pub quote_oracle: Option<Pubkey>,
     /// The ticker is used to pull the price from the Coinbase API, and therefore should conform to the Coinbase ticker format.
     /// Note that for all USDC quoted markets, the price feed should use "USD" instead of "USDC".
-    #[serde(deserialize_with = "deserialize_ticker")]
+    #[serde(
+        deserialize_with = "deserialize_ticker",
+        serialize_with = "serialize_ticker"
+    )]
     pub ticker: Ticker,
+    #[serde(deserialize_with = "deserialize_in_range::<_, 1, { u64::MAX }>")]
     pub quote_refresh_frequency_in_ms: u64,
+    /// Minimum slots between two quote updates, enforced on chain. 0 disables the limit.
+    #[serde(default)]
+    pub min_slots_between_updates: u64,
+    /// Distance of the quotes from the fair price, in bps.
+    #[serde(deserialize_with = "deserialize_in_range::<_, 1, 9_999>")]
     pub quote_edge_in_bps: u64,
-    pub quote_size: u64,
-    pub price_improvement_behavior: String,
+    /// Quote size in quote atoms, or in quote units such as "100 USDC".
+    #[serde(deserialize_with = "deserialize_positive_amount")]
+    pub quote_size: Amount,
+    /// Floors the edge at this multiple of the market taker fee, in bps (10000 = 1x). 0 disables the fee floor.
+    #[serde(default)]
+    pub taker_fee_edge_multiplier_in_bps: u64,
+    /// Cost of hedging a fill in bps, added to the fee floor.
+    #[serde(default, deserialize_with = "deserialize_in_range::<_, 0, 9_999>")]
+    pub hedge_cost_in_bps: u64,
+    /// How quotes react to the best competing orders.
+    pub price_improvement_behavior: PriceImprovementKind,
+    /// Ticks to step ahead of the best competing order with "penny-jump-capped".
+    #[serde(default)]
+    pub penny_jump_max_ticks: u64,
+    /// Minimum distance from fair value in bps that "penny-jump-capped" never quotes inside of.
+    #[serde(default, deserialize_with = "deserialize_in_range::<_, 0, 9_999>")]
+    pub penny_jump_min_edge_in_bps: u64,
     pub post_only: bool,
+    /// Reject post-only orders that would cross instead of sliding them to the best non-crossing price.
+    #[serde(default)]
+    pub reject_post_only: bool,
+    /// Self-trade behavior of limit orders: "abort", "cancel-provide" or "decrement-take".
+    #[serde(default)]
+    pub self_trade_behavior: SelfTradeKind,
+    /// Cancel resting orders of the trader that the strategy does not track on every update.
+    #[serde(default)]
+    pub cancel_untracked_orders: bool,
+    /// Oracle price used as fair value: "spot", "ema" or "blend".
+    #[serde(default)]
+    pub fair_price_mode: FairPriceKind,
+    /// Weight of the EMA price when `fair_price_mode` is "blend", 10000 is EMA only.
+    #[serde(default, deserialize_with = "deserialize_in_range::<_, 0, 10_000>")]
+    pub ema_weight_in_bps: u64,
+    /// How quotes are placed around the fair price: "fixed-edge" or "avellaneda-stoikov".
+    #[serde(default)]
+    pub quote_model: QuoteModelKind,
+    /// Avellaneda–Stoikov risk aversion γ in millionths per bps.
+    #[serde(default)]
+    pub risk_aversion_ppm: u64,
+    /// Avellaneda–Stoikov order arrival intensity decay k in millionths per bps.
+    #[serde(default)]
+    pub order_arrival_intensity_ppm: u64,
+    /// Avellaneda–Stoikov horizon the inventory risk is priced over.
+    #[serde(default)]
+    pub horizon_in_seconds: u64,
+    /// Half-life of the on-chain volatility estimate, 0 keeps only the latest oracle return.
+    #[serde(default = "default_volatility_half_life_in_seconds")]
+    pub volatility_half_life_in_seconds: u64,
+    /// Number of price levels per side of other traders' orders used for the book microprice.
+    #[serde(
+        default = "default_microprice_levels",
+        deserialize_with = "deserialize_in_range::<_, 1, { u64::MAX }>"
+    )]
+    pub microprice_levels: u64,
+    /// Weight of the book microprice against the oracle price in bps, 0 quotes around the oracle price only.
+    #[serde(default, deserialize_with = "deserialize_in_range::<_, 0, 10_000>")]
+    pub microprice_weight_in_bps: u64,
+    /// Circuit breaker: maximum base lots, or base units such as "10 SOL", filled within
+    /// `fill_window_in_slots`. 0 disables the check.
+    #[serde(default)]
+    pub max_base_lots_filled_per_window: Amount,
+    /// Circuit breaker: length of the fill window in slots.
+    #[serde(default)]
+    pub fill_window_in_slots: u64,
+    /// Circuit breaker: maximum loss since the last reset, marked at the spot oracle price, in quote
+    /// atoms or quote units such as "50 USDC". 0 disables the check.
+    #[serde(default)]
+    pub max_loss_in_quote_atoms: Amount,
+    /// Circuit breaker: maximum consecutive fills on the same side. 0 disables the check.
+    #[serde(default)]
+    pub max_consecutive_one_sided_fills: u64,
+    /// Time windows that override the quote edge and size, pushed on chain by `pomm set-schedule`.
+    #[serde(default)]
+    pub schedule: Vec<ScheduleProfileConfig>,
 }
 
-impl PhoenixOnChainMMConfig {
-    pub fn get_base_oracle_account(&self) -> anyhow::Result<Pubkey> {
-        get_oracle_account_by_name(self.ticker.base.as_str().to_lowercase().as_str())
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleProfileConfig {
+    /// Days the window starts on, e.g. ["mon", "fri"]. All days when empty.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// UTC start of the window as "HH:MM", inclusive
+    #[serde(deserialize_with = "deserialize_time_of_day")]
+    pub start: String,
+    /// UTC end of the window as "HH:MM", exclusive. Windows ending before they start wrap past midnight.
+    #[serde(deserialize_with = "deserialize_time_of_day")]
+    pub end: String,
+    /// Edge while the profile is active, 0 keeps quote_edge_in_bps
+    #[serde(default, deserialize_with = "deserialize_in_range::<_, 0, 9_999>")]
+    pub quote_edge_in_bps: u64,
+    /// Size while the profile is active in quote atoms or quote units, 0 keeps quote_size
+    #[serde(default)]
+    pub quote_size: Amount,
+}
+
+impl ScheduleProfileConfig {
+    pub fn to_params(
+        &self,
+        header: &MarketHeader,
+        ticker: &Ticker,
+    ) -> anyhow::Result<ScheduleProfileParams> {
+        let day_mask = if self.days.is_empty() {
+            0b111_1111
+        } else {
+            self.days
+                .iter()
+                .fold(0u8, |mask, day| mask | 1 << *day as u8)
+        };
+        Ok(ScheduleProfileParams {
+            day_mask,
+            start_minute_of_day: parse_minute_of_day(&self.start)?,
+            end_minute_of_day: parse_minute_of_day(&self.end)?,
+            quote_edge_in_bps: self.quote_edge_in_bps,
+            quote_size_in_quote_atoms: self.quote_size.to_quote_atoms(header, ticker)?,
+        })
     }
+}
 
-    pub fn get_quote_oracle_account(&self) -> anyhow::Result<Pubkey> {
-        get_oracle_account_by_name(self.ticker.quote.as_str().to_lowercase().as_str())
+/// Day of the week of a schedule window, numbered from Sunday like the on-chain day mask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    #[serde(alias = "sunday")]
+    Sun,
+    #[serde(alias = "monday")]
+    Mon,
+    #[serde(alias = "tuesday")]
+    Tue,
+    #[serde(alias = "wednesday")]
+    Wed,
+    #[serde(alias = "thursday")]
+    Thu,
+    #[serde(alias = "friday")]
+    Fri,
+    #[serde(alias = "saturday")]
+    Sat,
+}
+
+/// `price_improvement_behavior` setting, the penny-jump parameters are separate keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PriceImprovementKind {
+    #[serde(alias = "Join")]
+    Join,
+    #[serde(alias = "Dime")]
+    Dime,
+    #[serde(alias = "Ignore")]
+    Ignore,
+    #[serde(alias = "PennyJumpCapped")]
+    PennyJumpCapped,
+}
+
+/// `self_trade_behavior` setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelfTradeKind {
+    Abort,
+    #[default]
+    CancelProvide,
+    DecrementTake,
+}
+
+/// `fair_price_mode` setting, the blend weight is `ema_weight_in_bps`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FairPriceKind {
+    #[default]
+    Spot,
+    Ema,
+    Blend,
+}
+
+/// `quote_model` setting, the Avellaneda–Stoikov parameters are separate keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuoteModelKind {
+    #[default]
+    FixedEdge,
+    AvellanedaStoikov,
+}
+
+/// Rejects integers outside of `MIN..=MAX`.
+fn deserialize_in_range<'de, D, const MIN: u64, const MAX: u64>(
+    deserializer: D,
+) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = u64::deserialize(deserializer)?;
+    if value < MIN {
+        return Err(serde::de::Error::custom(format!(
+            "{} is below the minimum of {}",
+            value, MIN
+        )));
+    }
+    if value > MAX {
+        return Err(serde::de::Error::custom(format!(
+            "{} is above the maximum of {}",
+            value, MAX
+        )));
     }
+    Ok(value)
 }
 
-fn get_oracle_account_by_name(name: &str) -> anyhow::Result<Pubkey> {
-    match name {
-        "sol" => Ok(ids::sol_oracle::id()),
-        "eth" => Ok(ids::eth_oracle::id()),
-        "bonk" => Ok(ids::bonk_oracle::id()),
-        "usdc" => Ok(ids::usdc_oracle::id()),
-        "msol" => Ok(ids::msol_oracle::id()),
-        "jitosol" => Ok(ids::jitosol_oracle::id()),
-        _ => Err(anyhow::anyhow!(
-            "Invalid base({}) currency for oracle account",
-            name
-        )),
+fn deserialize_positive_amount<'de, D>(deserializer: D) -> Result<Amount, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let amount = Amount::deserialize(deserializer)?;
+    if amount.is_zero() {
+        return Err(serde::de::Error::custom("amount should be above 0"));
     }
+    Ok(amount)
+}
+
+fn deserialize_time_of_day<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let time = String::deserialize(deserializer)?;
+    parse_minute_of_day(&time).map_err(serde::de::Error::custom)?;
+    Ok(time)
+}
+
+/// Parses "HH:MM" into minutes after midnight, "24:00" is read as midnight.
+fn parse_minute_of_day(time: &str) -> anyhow::Result<u16> {
+    let invalid = || anyhow!("Invalid schedule time({}), expected HH:MM", time);
+    let (hours, minutes) = time.split_once(':').ok_or_else(invalid)?;
+    let hours = hours.trim().parse::<u16>().map_err(|_| invalid())?;
+    let minutes = minutes.trim().parse::<u16>().map_err(|_| invalid())?;
+    if hours > 24 || minutes >= 60 || (hours == 24 && minutes > 0) {
+        return Err(invalid());
+    }
+    Ok((hours * 60 + minutes) % (24 * 60))
+}
+
+impl PhoenixOnChainMMConfig {
+    /// Returns `base_oracle`, falling back to the oracle of the base symbol on `cluster`.
+    pub fn get_base_oracle_account(
+        &self,
+        oracle_registry: &OracleRegistry,
+        cluster: Cluster,
+    ) -> anyhow::Result<Pubkey> {
+        match self.base_oracle {
+            Some(base_oracle) => Ok(base_oracle),
+            None => oracle_registry.get(&self.ticker.base, cluster),
+        }
+    }
+
+    /// Returns `quote_oracle`, falling back to the oracle of the quote symbol on `cluster`.
+    pub fn get_quote_oracle_account(
+        &self,
+        oracle_registry: &OracleRegistry,
+        cluster: Cluster,
+    ) -> anyhow::Result<Pubkey> {
+        match self.quote_oracle {
+            Some(quote_oracle) => Ok(quote_oracle),
+            None => oracle_registry.get(&self.ticker.quote, cluster),
+        }
+    }
+
+    /// Returns `strategy`, falling back to the strategy `creator` creates on the market with
+    /// `program_id`.
+    pub fn get_strategy_address(&self, program_id: &Pubkey, creator: &Pubkey) -> Pubkey {
+        self.strategy.unwrap_or_else(|| {
+            get_strategy_address_with_program_id(program_id, creator, &self.market)
+        })
+    }
+
+    /// Returns the base and quote token accounts, falling back to the associated token accounts
+    /// of `owner` for the market mints under `token_program`.
+    pub fn get_token_accounts(
+        &self,
+        owner: &Pubkey,
+        header: &MarketHeader,
+        token_program: &Pubkey,
+    ) -> (Pubkey, Pubkey) {
+        let base_token_account = self.base_token_account.unwrap_or_else(|| {
+            get_associated_token_address_with_program_id(
+                owner,
+                &header.base_params.mint_key,
+                token_program,
+            )
+        });
+        let quote_token_account = self.quote_token_account.unwrap_or_else(|| {
+            get_associated_token_address_with_program_id(
+                owner,
+                &header.quote_params.mint_key,
+                token_program,
+            )
+        });
+        (base_token_account, quote_token_account)
+    }
+
+    /// Rejects sizes that buy less than one base lot at `fair_price_in_ticks`, the quote size as
+    /// well as the scheduled ones, so the strategy never places empty orders.
+    pub fn check_market(
+        &self,
+        header: &MarketHeader,
+        fair_price_in_ticks: u64,
+    ) -> anyhow::Result<()> {
+        if fair_price_in_ticks == 0 {
+            return Err(anyhow!(
+                "The fair price of {} is below one tick of {} quote atoms",
+                self.ticker,
+                header.get_tick_size_in_quote_atoms_per_base_unit()
+            ));
+        }
+        let market_params = get_market_params(header)?;
+        let mut sizes = vec![(
+            "quote_size".to_string(),
+            self.quote_edge_in_bps,
+            self.get_quote_size(header)?,
+        )];
+        for (index, profile) in self.schedule.iter().enumerate() {
+            let quote_size = profile.quote_size.to_quote_atoms(header, &self.ticker)?;
+            if quote_size == 0 {
+                continue;
+            }
+            let edge_in_bps = match profile.quote_edge_in_bps {
+                0 => self.quote_edge_in_bps,
+                edge_in_bps => edge_in_bps,
+            };
+            sizes.push((
+                format!("schedule.{}.quote_size", index),
+                edge_in_bps,
+                quote_size,
+            ));
+        }
+        for (key, edge_in_bps, quote_size) in sizes {
+            // The ask is the highest price quoted, so it buys the fewest lots
+            let ask_price_in_ticks = ask_price_in_ticks(fair_price_in_ticks, edge_in_bps)?;
+            if size_in_base_lots(quote_size, ask_price_in_ticks, &market_params)? == 0 {
+                return Err(anyhow!(
+                    "{} of {} quote atoms is less than one base lot of {} at {} ticks",
+                    key,
+                    quote_size,
+                    self.ticker,
+                    ask_price_in_ticks
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_price_improvement_behavior(&self) -> PriceImprovementBehavior {
+        match self.price_improvement_behavior {
+            PriceImprovementKind::Join => PriceImprovementBehavior::Join,
+            PriceImprovementKind::Dime => PriceImprovementBehavior::Dime,
+            PriceImprovementKind::Ignore => PriceImprovementBehavior::Ignore,
+            PriceImprovementKind::PennyJumpCapped => PriceImprovementBehavior::PennyJumpCapped {
+                max_ticks: self.penny_jump_max_ticks,
+                min_edge_bps: self.penny_jump_min_edge_in_bps,
+            },
+        }
+    }
+
+    pub fn get_self_trade_behavior(&self) -> SelfTradeBehavior {
+        match self.self_trade_behavior {
+            SelfTradeKind::Abort => SelfTradeBehavior::Abort,
+            SelfTradeKind::CancelProvide => SelfTradeBehavior::CancelProvide,
+            SelfTradeKind::DecrementTake => SelfTradeBehavior::DecrementTake,
+        }
+    }
+
+    pub fn get_quote_model(&self) -> anyhow::Result<QuoteModel> {
+        match self.quote_model {
+            QuoteModelKind::FixedEdge => Ok(QuoteModel::FixedEdge),
+            QuoteModelKind::AvellanedaStoikov => {
+                let quote_model = QuoteModel::AvellanedaStoikov {
+                    risk_aversion_ppm: self.risk_aversion_ppm,
+                    order_arrival_intensity_ppm: self.order_arrival_intensity_ppm,
+                    horizon_in_seconds: self.horizon_in_seconds,
+                };
+                if !quote_model.is_valid() {
+                    return Err(anyhow!(
+                        "avellaneda-stoikov needs non-zero risk_aversion_ppm and order_arrival_intensity_ppm"
+                    ));
+                }
+                Ok(quote_model)
+            }
+        }
+    }
+
+    pub fn get_schedule_profiles(
+        &self,
+        header: &MarketHeader,
+    ) -> anyhow::Result<Vec<ScheduleProfileParams>> {
+        if self.schedule.len() > MAX_SCHEDULE_PROFILES {
+            return Err(anyhow!(
+                "At most {} schedule profiles are supported, got {}",
+                MAX_SCHEDULE_PROFILES,
+                self.schedule.len()
+            ));
+        }
+        self.schedule
+            .iter()
+            .map(|profile| profile.to_params(header, &self.ticker))
+            .collect()
+    }
+
+    /// Returns `quote_size` in quote atoms.
+    pub fn get_quote_size(&self, header: &MarketHeader) -> anyhow::Result<u64> {
+        self.quote_size.to_quote_atoms(header, &self.ticker)
+    }
+
+    /// Returns `max_loss_in_quote_atoms` in quote atoms.
+    pub fn get_max_loss_in_quote_atoms(&self, header: &MarketHeader) -> anyhow::Result<u64> {
+        self.max_loss_in_quote_atoms
+            .to_quote_atoms(header, &self.ticker)
+    }
+
+    /// Returns `max_base_lots_filled_per_window` in base lots.
+    pub fn get_max_base_lots_filled_per_window(
+        &self,
+        header: &MarketHeader,
+    ) -> anyhow::Result<u64> {
+        self.max_base_lots_filled_per_window
+            .to_base_lots(header, &self.ticker)
+    }
+
+    pub fn get_fair_price_mode(&self) -> FairPriceMode {
+        match self.fair_price_mode {
+            FairPriceKind::Spot => FairPriceMode::Spot,
+            FairPriceKind::Ema => FairPriceMode::Ema,
+            FairPriceKind::Blend => FairPriceMode::Blend {
+                ema_weight_in_bps: self.ema_weight_in_bps,
+            },
+        }
+    }
+}
+
+fn default_volatility_half_life_in_seconds() -> u64 {
+    300
+}
+
+fn default_microprice_levels() -> u64 {
+    1
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -136,6 +1178,13 @@ where
     })
 }
 
+fn serialize_ticker<S>(ticker: &Ticker, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&ticker.to_string())
+}
+
 pub fn get_network(network_str: &str) -> &str {
     match network_str {
         "devnet" | "dev" | "d" => "https://api.devnet.solana.com",
@@ -161,3 +1210,228 @@ fn test_read_config() {
 
     println!("{:#?}", config);
 }
+
+#[test]
+fn test_select_profile() {
+    let config_str = format!(
+        "{}\n{}",
+        crate::constant::DEFAULT_CONFIG_FILE,
+        r#"
+[profiles.main-wide]
+rpc_endpoint = "main"
+
+[profiles.main-wide.phoenix]
+quote_edge_in_bps = 9
+"#
+    );
+
+    // The top-level [phoenix] section is used when no profile is chosen
+    let config = Config::from_toml_str(&config_str, None).unwrap();
+    assert_eq!(config.phoenix.quote_edge_in_bps, 3);
+
+    // The profile overrides single keys and keeps the rest of the top-level settings
+    let config = Config::from_toml_str(&config_str, Some("main-wide")).unwrap();
+    assert_eq!(config.rpc_endpoint.as_deref(), Some("main"));
+    assert_eq!(config.phoenix.quote_edge_in_bps, 9);
+    assert_eq!(
+        config.phoenix.market.to_string(),
+        "CS2H8nbAVVEUHWPF5extCSymqheQdkd4d7thik6eet9N"
+    );
+
+    assert!(Config::from_toml_str(&config_str, Some("devnet")).is_err());
+}
+
+#[test]
+fn test_markets_inherit_phoenix() {
+    let config_str = format!(
+        "{}\n{}",
+        crate::constant::DEFAULT_CONFIG_FILE,
+        r#"
+[[markets]]
+
+[[markets]]
+market = "4DoNfFBfF7UokCC2FQzriy7yHK6DY6NVdYpuekQ5pRgg"
+ticker = "BONK/USDC"
+quote_edge_in_bps = 20
+"#
+    );
+    let config = Config::from_toml_str(&config_str, None).unwrap();
+    let markets = config.get_markets();
+    assert_eq!(markets.len(), 2);
+    assert_eq!(markets[0].market, config.phoenix.market);
+    assert_eq!(markets[0].quote_edge_in_bps, 3);
+    assert_eq!(markets[1].ticker.to_string(), "BONK/USDC");
+    assert_eq!(markets[1].quote_edge_in_bps, 20);
+    assert_eq!(markets[1].quote_size, config.phoenix.quote_size);
+}
+
+#[test]
+fn test_overrides_take_precedence() {
+    let env = vec![
+        ("POMM_RPC_ENDPOINT".to_string(), "main".to_string()),
+        (
+            "POMM_PHOENIX_QUOTE_EDGE_IN_BPS".to_string(),
+            "7".to_string(),
+        ),
+        ("POMM_PHOENIX_POST_ONLY".to_string(), "false".to_string()),
+        ("POMM_PROFILE".to_string(), "devnet".to_string()),
+        ("OTHER".to_string(), "1".to_string()),
+    ];
+    let mut overrides = ConfigOverride::from_env(env);
+    assert_eq!(overrides.len(), 3);
+    overrides.push(ConfigOverride::from_cli("phoenix.quote_edge_in_bps=9").unwrap());
+    let config =
+        Config::from_layers(crate::constant::DEFAULT_CONFIG_FILE, None, &overrides).unwrap();
+
+    assert_eq!(config.rpc_endpoint.as_deref(), Some("main"));
+    assert!(!config.phoenix.post_only);
+    assert_eq!(config.phoenix.quote_edge_in_bps, 9);
+    assert_eq!(
+        config.get_source("phoenix.quote_edge_in_bps"),
+        ConfigSource::Cli
+    );
+    assert_eq!(
+        config.get_source("rpc_endpoint"),
+        ConfigSource::Env("POMM_RPC_ENDPOINT".to_string())
+    );
+    assert_eq!(config.get_source("phoenix.market"), ConfigSource::File);
+    assert_eq!(
+        config.get_source("phoenix.penny_jump_max_ticks"),
+        ConfigSource::File
+    );
+    assert_eq!(config.get_source("phoenix.strategy"), ConfigSource::Default);
+    assert!(ConfigOverride::from_cli("phoenix.quote_edge_in_bps").is_err());
+}
+
+#[test]
+fn test_indexed_overrides() {
+    let config_str = format!(
+        "{}\n{}",
+        crate::constant::DEFAULT_CONFIG_FILE,
+        r#"
+[[markets]]
+quote_edge_in_bps = 20
+"#
+    );
+    let program_id = Pubkey::new_unique();
+    let env = vec![
+        (
+            "POMM_PROGRAM_IDS_MAINNET_BETA".to_string(),
+            program_id.to_string(),
+        ),
+        (
+            "POMM_MARKETS_0_QUOTE_EDGE_IN_BPS".to_string(),
+            "7".to_string(),
+        ),
+    ];
+    let mut overrides = ConfigOverride::from_env(env);
+    assert_eq!(overrides[0].key, "markets.0.quote_edge_in_bps");
+    assert_eq!(overrides[1].key, "program_ids.mainnet-beta");
+    overrides.push(ConfigOverride::from_cli("markets.1.ticker=BONK/USDC").unwrap());
+    let config = Config::from_layers(&config_str, None, &overrides).unwrap();
+
+    assert_eq!(
+        config.program_ids.get(&Cluster::MainnetBeta),
+        Some(&program_id)
+    );
+    let markets = config.get_markets();
+    assert_eq!(markets.len(), 2);
+    assert_eq!(markets[0].quote_edge_in_bps, 7);
+    assert_eq!(
+        config.get_source("markets.0.quote_edge_in_bps"),
+        ConfigSource::Env("POMM_MARKETS_0_QUOTE_EDGE_IN_BPS".to_string())
+    );
+    // An index right past the end appends a market inheriting [phoenix]
+    assert_eq!(markets[1].ticker.to_string(), "BONK/USDC");
+    assert_eq!(markets[1].quote_edge_in_bps, 3);
+
+    let overrides = [ConfigOverride::from_cli("markets.2.quote_edge_in_bps=7").unwrap()];
+    let error = Config::from_layers(&config_str, None, &overrides)
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        error,
+        "Can't set markets.2.quote_edge_in_bps, expected an index of markets up to 1"
+    );
+}
+
+#[test]
+fn test_invalid_values_are_located() {
+    let config_str = crate::constant::DEFAULT_CONFIG_FILE.replace(
+        r#"price_improvement_behavior = "ignore""#,
+        r#"price_improvement_behavior = "jion""#,
+    );
+    let line = config_str
+        .lines()
+        .position(|line| line.starts_with("price_improvement_behavior"))
+        .unwrap()
+        + 1;
+    let error = Config::from_toml_str(&config_str, None)
+        .unwrap_err()
+        .to_string();
+    assert!(error.starts_with(&format!(
+        "Invalid phoenix.price_improvement_behavior at line {}, column 30: unknown variant `jion`",
+        line
+    )));
+
+    let config_str = format!(
+        "{}\n[[markets]]\nmarket = \"4DoNfFBfF7UokCC2FQzriy7yHK6DY6NVdYpuekQ5pRgg\"\nticker = \"BONK/USDC\"\nquote_size = 0\n",
+        crate::constant::DEFAULT_CONFIG_FILE
+    );
+    let error = Config::from_toml_str(&config_str, None)
+        .unwrap_err()
+        .to_string();
+    assert!(error.starts_with("Invalid markets.0.quote_size at line"));
+    assert!(error.ends_with("amount should be above 0"));
+
+    let overrides = [ConfigOverride::from_cli("phoenix.quote_edge_in_bps=10000").unwrap()];
+    let error = Config::from_layers(crate::constant::DEFAULT_CONFIG_FILE, None, &overrides)
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        error,
+        "Invalid phoenix.quote_edge_in_bps set by --set: 10000 is above the maximum of 9999"
+    );
+}
+
+#[test]
+fn test_unknown_keys_are_rejected() {
+    let config_str = crate::constant::DEFAULT_CONFIG_FILE.replace(
+        "quote_edge_in_bps = 3",
+        "quote_edge_in_bps = 3\nquote_egde_in_bps = 5",
+    );
+    let line = config_str
+        .lines()
+        .position(|line| line.starts_with("quote_egde_in_bps"))
+        .unwrap()
+        + 1;
+    let error = Config::from_toml_str(&config_str, None)
+        .unwrap_err()
+        .to_string();
+    assert!(error.starts_with(&format!(
+        "Invalid phoenix.quote_egde_in_bps at line {}, column 21: unknown field `quote_egde_in_bps`",
+        line
+    )));
+
+    // A misspelled POMM_* variable fails instead of being ignored
+    let env = vec![("POMM_RPC".to_string(), "main".to_string())];
+    let error = Config::from_layers(
+        crate::constant::DEFAULT_CONFIG_FILE,
+        None,
+        &ConfigOverride::from_env(env),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(error.starts_with("Invalid rpc set by POMM_RPC: unknown field `rpc`"));
+
+    let env = vec![("POMM_PHOENIX_QUOTE_EDGE".to_string(), "7".to_string())];
+    let error = Config::from_layers(
+        crate::constant::DEFAULT_CONFIG_FILE,
+        None,
+        &ConfigOverride::from_env(env),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(error
+        .starts_with("Invalid phoenix.quote_edge set by POMM_PHOENIX_QUOTE_EDGE: unknown field"));
+}
@@ -1,7 +1,32 @@
-pub const PHOENIX_ONCHAIN_MM_STRATEGY_SEED: &[u8] = b"phoenix";
-pub const PHOENIX_ONCHAIN_MM_ORACLE_SEED: &[u8] = b"oracle";
 pub const BASE: f64 = 10.0;
 
+/// Built-in oracle registry, Pyth price accounts by token symbol and cluster.
+pub const DEFAULT_ORACLES_FILE: &str = r#"
+[sol]
+devnet = "J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix"
+mainnet-beta = "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG"
+
+[usdc]
+devnet = "5SSkXsEKQepHHAewytPVwdej4epN1nxgLVM84L4KXgy7"
+mainnet-beta = "Gnt27xtC473ZT2Mw5u8wZ68Z3gULkSTb5DuxJy7eJotD"
+
+[eth]
+devnet = "EdVCmQ9FSPcVe5YySXDPCRmc8aDQLKJ9xvYBMZPie1Vw"
+mainnet-beta = "JBu1AL4obBcCMqKBBxhpWCNUt136ijcuMZLFvTP7iWdB"
+
+[bonk]
+devnet = "6bquU99ktV1VRiHDr8gMhDFt3kMfhCQo5nfNrg2Urvsn"
+mainnet-beta = "8ihFLu5FimgTQ1Unh4dVyEHUGodJ5gJQCrQf4KUVB9bN"
+
+[msol]
+devnet = "9a6RNx3tCu1TSs6TBSfV2XRXEPEZXQ6WB7jRojZRvyeZ"
+mainnet-beta = "E4v1BBgoso9s64TQvmyownAVJbhbEPGyzA3qn4n46qj9"
+
+[jitosol]
+devnet = "3d4eLK2TF6UdpSjKvS5ZUnDY1uZq2sEj9Tk3cujpUaAk"
+mainnet-beta = "7yyaeuJ1GGtVBLT2z2xub5ZWYKaNhF28mj1RdV4VDFVk"
+"#;
+
 pub const DEFAULT_CONFIG_FILE: &str = r#"
 # Optionally include your keypair path. Defaults to your Solana CLI config file.
 keypair_path = "~/.config/solana/id.json"
@@ -9,12 +34,78 @@ keypair_path = "~/.config/solana/id.json"
 rpc_endpoint = "https://api.devnet.solana.com"
 # Optionally include a commitment level. Defaults to your Solana CLI config file.
 commitment = "confirmed"
+# Optionally link transactions to another explorer, {signature} is replaced by the transaction signature.
+# Defaults to explorer.solana.com for the cluster of the RPC endpoint.
+# explorer_url = "http://localhost:3000/tx/{signature}"
+
+# Optionally use another deployment of the program on a cluster: mainnet-beta, devnet, testnet or localnet
+# [program_ids]
+# localnet = "..."
 
 [phoenix]
 market = "CS2H8nbAVVEUHWPF5extCSymqheQdkd4d7thik6eet9N"
+# Set the strategy address once your keypair is not the one that initialized it, e.g. after `pomm set-trader`
+# strategy = ""
+# Trade from these token accounts instead of your associated token accounts, e.g. a treasury account
+# base_token_account = "..."
+# quote_token_account = "..."
+# Price oracles, by default looked up by the ticker symbols in the oracle registry, see `pomm oracles list`
+# base_oracle = "..."
+# quote_oracle = "..."
 ticker = "SOL/USDC"
 quote_refresh_frequency_in_ms = 2000
+# Updates landing less than this many slots after the previous one are skipped on chain, 0 disables it
+min_slots_between_updates = 0
 quote_edge_in_bps = 3
+# In quote atoms, or in quote token units such as "100 USDC"
 quote_size = 100000000
+# Floor the edge at taker_fee * taker_fee_edge_multiplier_in_bps / 10000 + hedge_cost_in_bps, 0 and 0 disable it
+taker_fee_edge_multiplier_in_bps = 0
+hedge_cost_in_bps = 0
+# "join", "dime", "ignore" or "penny-jump-capped"
 price_improvement_behavior = "ignore"
-post_only = true"#;
+# penny-jump-capped steps this many ticks ahead of the best competing order, never inside the minimum edge
+penny_jump_max_ticks = 1
+penny_jump_min_edge_in_bps = 2
+post_only = true
+# Post-only orders that would cross slide to the best non-crossing price unless reject_post_only is set
+reject_post_only = false
+# Limit orders (post_only = false with "join") resolve self trades with "abort", "cancel-provide" or "decrement-take"
+self_trade_behavior = "cancel-provide"
+# Cancel resting orders the strategy does not track (e.g. left by a failed transaction) on every update, see also `pomm reconcile`
+cancel_untracked_orders = false
+# Fair value from the Pyth "spot" price, its "ema" price, or a "blend" weighted by ema_weight_in_bps
+fair_price_mode = "spot"
+ema_weight_in_bps = 0
+# Quotes sit quote_edge_in_bps around the fair price with "fixed-edge". "avellaneda-stoikov" skews them against
+# the inventory since the last circuit breaker reset, keeping quote_edge_in_bps as the minimum half spread.
+# Risk aversion and order arrival intensity are in millionths per bps, inventory is counted in quote sizes.
+quote_model = "fixed-edge"
+risk_aversion_ppm = 0
+order_arrival_intensity_ppm = 0
+horizon_in_seconds = 0
+# Half-life of the on-chain EWMA volatility estimate of oracle returns
+volatility_half_life_in_seconds = 300
+# Blend the fair price with the microprice of the top microprice_levels levels of other traders' orders
+microprice_levels = 3
+microprice_weight_in_bps = 0
+# Circuit breaker limits, 0 disables a check. Once tripped, run `pomm reset-circuit-breaker`.
+# The size limits also take token units, e.g. "10 SOL" and "50 USDC".
+# Set by `pomm init`, push changes on chain with `pomm set-circuit-breaker` signed by the admin.
+max_base_lots_filled_per_window = 0
+fill_window_in_slots = 150
+max_loss_in_quote_atoms = 0
+max_consecutive_one_sided_fills = 0
+# Optional UTC windows overriding quote_edge_in_bps and quote_size (0 keeps them), at most 4, first match wins.
+# There is no per-window number of levels, the strategy always quotes one order per side.
+# Push changes on chain with `pomm set-schedule` signed by the admin.
+# [[phoenix.schedule]]
+# days = ["mon", "tue", "wed", "thu", "fri"] # all days when omitted
+# start = "13:30"
+# end = "15:00"
+# quote_edge_in_bps = 8
+# quote_size = 0
+# Quote more markets from `pomm update-quotes`, each inheriting the [phoenix] keys it does not set
+# [[markets]]
+# market = "..."
+# ticker = "...""#;
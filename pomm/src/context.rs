@@ -0,0 +1,256 @@
+use crate::config::{Config as PhoenixConfig, PhoenixOnChainMMConfig};
+use crate::oracle::OracleRegistry;
+use crate::utils::{
+    detect_cluster, get_market_header, get_oracle_registry, get_pomm_config, get_token_program,
+    ClusterContext, ConfigSelection,
+};
+use ellipsis_client::EllipsisClient;
+use phoenix::program::MarketHeader;
+use phoenix_onchain_mm::client::{self, TokenAccounts};
+use phoenix_onchain_mm::oracle::OracleConfig;
+use phoenix_onchain_mm::{CircuitBreakerParams, StrategyParams};
+use phoenix_sdk::sdk_client::SDKClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The resolved config, the keypair, the oracle registry and one RPC connection to the detected
+/// cluster, shared by every command. Services can embed the market maker through it instead of
+/// running `pomm`.
+pub struct PommContext {
+    pub config: PhoenixConfig,
+    pub payer: Keypair,
+    /// Phoenix SDK client, its RPC client is the one returned by `client()`
+    pub sdk: SDKClient,
+    /// The detected cluster and the strategy program id on it
+    pub cluster: ClusterContext,
+    /// Oracles of the markets that set no `base_oracle` or `quote_oracle`
+    pub oracle_registry: OracleRegistry,
+    market_headers: Mutex<HashMap<Pubkey, MarketHeader>>,
+}
+
+impl PommContext {
+    /// Loads the config and the oracle registry chosen with `--config`, `--profile` and `--set`,
+    /// then connects to it.
+    pub async fn load(selection: &ConfigSelection) -> anyhow::Result<Self> {
+        Self::new(get_pomm_config(selection)?, get_oracle_registry(selection)?).await
+    }
+
+    /// Connects to the RPC endpoint of `config` and detects its cluster.
+    pub async fn new(
+        config: PhoenixConfig,
+        oracle_registry: OracleRegistry,
+    ) -> anyhow::Result<Self> {
+        let (commitment, payer, rpc_enpoint) = config.read_global_config()?;
+        let client = RpcClient::new_with_commitment(rpc_enpoint, commitment);
+        let cluster = detect_cluster(&config, &client).await?;
+        let sdk =
+            SDKClient::new_from_ellipsis_client(EllipsisClient::from_rpc(client, &payer)?).await?;
+        Ok(PommContext {
+            config,
+            payer,
+            sdk,
+            cluster,
+            oracle_registry,
+            market_headers: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn client(&self) -> &RpcClient {
+        &self.sdk.client
+    }
+
+    /// The `[phoenix]` market, used by every command but `update-quotes`.
+    pub fn market_config(&self) -> &PhoenixOnChainMMConfig {
+        &self.config.phoenix
+    }
+
+    /// Returns the header of `market`, fetched once.
+    pub async fn get_market_header(&self, market: &Pubkey) -> anyhow::Result<MarketHeader> {
+        if let Some(header) = self.market_headers.lock().unwrap().get(market) {
+            return Ok(*header);
+        }
+        let header = get_market_header(self.client(), market).await?;
+        self.market_headers.lock().unwrap().insert(*market, header);
+        Ok(header)
+    }
+
+    /// Returns the strategy of `market_config` operated by the keypair.
+    pub fn get_strategy_address(&self, market_config: &PhoenixOnChainMMConfig) -> Pubkey {
+        market_config.get_strategy_address(&self.cluster.program_id, &self.payer.pubkey())
+    }
+
+    /// Returns the base oracle of `market_config` on the detected cluster.
+    pub fn get_base_oracle_account(
+        &self,
+        market_config: &PhoenixOnChainMMConfig,
+    ) -> anyhow::Result<Pubkey> {
+        market_config.get_base_oracle_account(&self.oracle_registry, self.cluster.cluster)
+    }
+
+    /// Returns the quote oracle of `market_config` on the detected cluster.
+    pub fn get_quote_oracle_account(
+        &self,
+        market_config: &PhoenixOnChainMMConfig,
+    ) -> anyhow::Result<Pubkey> {
+        market_config.get_quote_oracle_account(&self.oracle_registry, self.cluster.cluster)
+    }
+
+    /// Returns the base and quote token accounts of the keypair for `market_config`.
+    pub async fn get_token_accounts(
+        &self,
+        market_config: &PhoenixOnChainMMConfig,
+    ) -> anyhow::Result<(Pubkey, Pubkey)> {
+        let header = self.get_market_header(&market_config.market).await?;
+        let token_program = get_token_program(self.client(), &header).await?;
+        Ok(market_config.get_token_accounts(&self.payer.pubkey(), &header, &token_program))
+    }
+
+    /// Builds the strategy parameters of `market_config`, sizes converted with its market header.
+    pub async fn get_strategy_params(
+        &self,
+        market_config: &PhoenixOnChainMMConfig,
+    ) -> anyhow::Result<StrategyParams> {
+        let header = self.get_market_header(&market_config.market).await?;
+        Ok(StrategyParams {
+            quote_edge_in_bps: Some(market_config.quote_edge_in_bps),
+            quote_size_in_quote_atoms: Some(market_config.get_quote_size(&header)?),
+            taker_fee_edge_multiplier_in_bps: Some(market_config.taker_fee_edge_multiplier_in_bps),
+            hedge_cost_in_bps: Some(market_config.hedge_cost_in_bps),
+            price_improvement_behavior: Some(market_config.get_price_improvement_behavior()),
+            post_only: Some(market_config.post_only),
+            reject_post_only: Some(market_config.reject_post_only),
+            self_trade_behavior: Some(market_config.get_self_trade_behavior()),
+            cancel_untracked_orders: Some(market_config.cancel_untracked_orders),
+            fair_price_mode: Some(market_config.get_fair_price_mode()),
+            quote_model: Some(market_config.get_quote_model()?),
+            volatility_half_life_in_seconds: Some(market_config.volatility_half_life_in_seconds),
+            min_slots_between_updates: Some(market_config.min_slots_between_updates),
+            microprice_levels: Some(market_config.microprice_levels),
+            microprice_weight_in_bps: Some(market_config.microprice_weight_in_bps),
+            oracle_account_config: OracleConfig {
+                oracle_base_account: self.get_base_oracle_account(market_config)?,
+                oracle_quote_account: self.get_quote_oracle_account(market_config)?,
+            },
+        })
+    }
+
+    /// Builds the circuit breaker limits of `market_config`, sizes converted with its market header.
+    pub async fn get_circuit_breaker_params(
+        &self,
+        market_config: &PhoenixOnChainMMConfig,
+    ) -> anyhow::Result<CircuitBreakerParams> {
+        let header = self.get_market_header(&market_config.market).await?;
+        Ok(CircuitBreakerParams {
+            max_base_lots_filled_per_window: market_config
+                .get_max_base_lots_filled_per_window(&header)?,
+            fill_window_in_slots: market_config.fill_window_in_slots,
+            max_loss_in_quote_atoms: market_config.get_max_loss_in_quote_atoms(&header)?,
+            max_consecutive_one_sided_fills: market_config.max_consecutive_one_sided_fills,
+        })
+    }
+
+    /// Builds the instruction creating the strategy of the keypair on `market_config`.
+    pub fn initialize_ix(
+        &self,
+        market_config: &PhoenixOnChainMMConfig,
+        params: StrategyParams,
+    ) -> Instruction {
+        // A new strategy always belongs to the keypair, whatever `strategy` is set to
+        client::initialize_ix_with_program_id(
+            &self.cluster.program_id,
+            &self.payer.pubkey(),
+            &market_config.market,
+            params,
+        )
+    }
+
+    /// Builds the instruction setting the circuit breaker limits of the strategy of `market_config`,
+    /// signed by the keypair as the admin.
+    pub fn set_circuit_breaker_ix(
+        &self,
+        market_config: &PhoenixOnChainMMConfig,
+        params: CircuitBreakerParams,
+    ) -> Instruction {
+        client::set_circuit_breaker_ix_with_program_id(
+            &self.cluster.program_id,
+            &self.get_strategy_address(market_config),
+            &self.payer.pubkey(),
+            params,
+        )
+    }
+
+    /// Builds the instruction upgrading the strategy of the keypair on `market_config` from the
+    /// layout that predates the strategy roles.
+    pub fn migrate_ix(&self, market_config: &PhoenixOnChainMMConfig) -> Instruction {
+        client::migrate_ix_with_program_id(
+            &self.cluster.program_id,
+            &self.payer.pubkey(),
+            &market_config.market,
+        )
+    }
+
+    /// Builds the instruction quoting `market_config` with `params`.
+    pub async fn update_quotes_ix(
+        &self,
+        market_config: &PhoenixOnChainMMConfig,
+        params: StrategyParams,
+    ) -> anyhow::Result<Instruction> {
+        let header = self.get_market_header(&market_config.market).await?;
+        let token_program = get_token_program(self.client(), &header).await?;
+        let (base_account, quote_account) =
+            market_config.get_token_accounts(&self.payer.pubkey(), &header, &token_program);
+        Ok(client::update_quotes_ix_with_custom_accounts(
+            &self.cluster.program_id,
+            &self.get_strategy_address(market_config),
+            &self.payer.pubkey(),
+            &market_config.market,
+            &header,
+            &TokenAccounts {
+                base_account,
+                quote_account,
+                token_program,
+            },
+            params,
+        ))
+    }
+
+    /// Builds the instruction cancelling every order of the keypair on `market_config` through
+    /// the strategy, which stops tracking its quotes.
+    pub fn cancel_all_ix(&self, market_config: &PhoenixOnChainMMConfig) -> Instruction {
+        client::cancel_all_ix_with_program_id(
+            &self.cluster.program_id,
+            &self.get_strategy_address(market_config),
+            &self.payer.pubkey(),
+            &market_config.market,
+        )
+    }
+
+    /// Signs `instructions` with the keypair, sends them and waits for confirmation.
+    pub async fn send_instructions(
+        &self,
+        instructions: &[Instruction],
+    ) -> anyhow::Result<Signature> {
+        let blockhash = self.client().get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+        Ok(self
+            .client()
+            .send_and_confirm_transaction(&transaction)
+            .await?)
+    }
+
+    /// Returns the explorer link of a transaction.
+    pub fn explorer_tx_url(&self, signature: &impl std::fmt::Display) -> String {
+        self.cluster.explorer_tx_url(signature)
+    }
+}
@@ -0,0 +1,15 @@
+//! Client of the Phoenix on-chain market maker. The `pomm` binary is a thin CLI over this
+//! crate, services can use `PommContext` to run the same commands in process.
+
+pub mod amount;
+pub mod cluster;
+pub mod command;
+pub mod config;
+pub mod constant;
+pub mod context;
+pub mod errors;
+pub mod ids;
+pub mod oracle;
+pub mod utils;
+
+pub use context::PommContext;
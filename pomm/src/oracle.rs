@@ -0,0 +1,182 @@
+use crate::cluster::Cluster;
+use crate::constant::DEFAULT_ORACLES_FILE;
+use anyhow::anyhow;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Where an oracle entry of the registry is defined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OracleSource {
+    BuiltIn,
+    File(PathBuf),
+}
+
+impl fmt::Display for OracleSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OracleSource::BuiltIn => write!(f, "built-in"),
+            OracleSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OracleEntry {
+    pub account: Pubkey,
+    pub source: OracleSource,
+}
+
+/// Oracle price accounts by lowercase token symbol, then by cluster.
+///
+/// The built-in entries can be extended or replaced by an `oracles.toml` file next to the config file:
+///
+/// ```toml
+/// [wif]
+/// devnet = "..."
+/// mainnet-beta = "..."
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OracleRegistry {
+    oracles: BTreeMap<String, BTreeMap<Cluster, OracleEntry>>,
+}
+
+impl OracleRegistry {
+    /// Returns the built-in oracles.
+    pub fn built_in() -> anyhow::Result<Self> {
+        let mut registry = Self::default();
+        registry.extend_from_str(DEFAULT_ORACLES_FILE, OracleSource::BuiltIn)?;
+        Ok(registry)
+    }
+
+    /// Returns the built-in oracles, extended by the registry file at `path` if it exists.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut registry = Self::built_in()?;
+        if path.exists() {
+            let registry_str = std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+            registry
+                .extend_from_str(&registry_str, OracleSource::File(path.to_path_buf()))
+                .map_err(|e| anyhow!("Invalid oracle registry {}: {}", path.display(), e))?;
+        }
+        Ok(registry)
+    }
+
+    /// Adds the oracles of a registry file, replacing entries for the same symbol and cluster.
+    pub fn extend_from_str(
+        &mut self,
+        registry_str: &str,
+        source: OracleSource,
+    ) -> anyhow::Result<()> {
+        let table = toml::from_str::<toml::Table>(registry_str)?;
+        for (symbol, clusters) in table {
+            let clusters = clusters
+                .as_table()
+                .ok_or_else(|| anyhow!("{} should be a table of cluster = \"<pubkey>\"", symbol))?;
+            for (cluster, account) in clusters {
+                let cluster = Cluster::from_str(cluster)
+                    .map_err(|e| anyhow!("{}.{}: {}", symbol, cluster, e))?;
+                let account = account
+                    .as_str()
+                    .and_then(|account| Pubkey::from_str(account).ok())
+                    .ok_or_else(|| anyhow!("{}.{} should be a pubkey", symbol, cluster))?;
+                self.oracles
+                    .entry(symbol.to_lowercase())
+                    .or_default()
+                    .insert(
+                        cluster,
+                        OracleEntry {
+                            account,
+                            source: source.clone(),
+                        },
+                    );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the oracle of `symbol` on `cluster`, symbols are case-insensitive.
+    pub fn get(&self, symbol: &str, cluster: Cluster) -> anyhow::Result<Pubkey> {
+        self.oracles
+            .get(&symbol.to_lowercase())
+            .and_then(|clusters| clusters.get(&cluster))
+            .map(|entry| entry.account)
+            .ok_or_else(|| {
+                anyhow!(
+                    "No {} oracle for {}, add it to the oracle registry or set base_oracle/quote_oracle",
+                    cluster,
+                    symbol
+                )
+            })
+    }
+
+    /// Lists every entry as (symbol, cluster, entry).
+    pub fn entries(&self) -> impl Iterator<Item = (&str, Cluster, &OracleEntry)> {
+        self.oracles.iter().flat_map(|(symbol, clusters)| {
+            clusters
+                .iter()
+                .map(move |(cluster, entry)| (symbol.as_str(), *cluster, entry))
+        })
+    }
+}
+
+#[test]
+fn test_oracle_registry() {
+    let mut registry = OracleRegistry::built_in().unwrap();
+    assert_eq!(
+        registry.get("SOL", Cluster::Devnet).unwrap().to_string(),
+        "J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix"
+    );
+    assert!(registry.get("wif", Cluster::MainnetBeta).is_err());
+
+    let path = PathBuf::from("oracles.toml");
+    registry
+        .extend_from_str(
+            r#"
+            [WIF]
+            mainnet-beta = "6ABgrEZk8urs6kJ1JNdC1sspH5zKXRqxy8sg3ZG2cQps"
+
+            [sol]
+            devnet = "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG"
+            "#,
+            OracleSource::File(path.clone()),
+        )
+        .unwrap();
+    assert_eq!(
+        registry
+            .get("wif", Cluster::MainnetBeta)
+            .unwrap()
+            .to_string(),
+        "6ABgrEZk8urs6kJ1JNdC1sspH5zKXRqxy8sg3ZG2cQps"
+    );
+    let (_, _, entry) = registry
+        .entries()
+        .find(|(symbol, cluster, _)| *symbol == "sol" && *cluster == Cluster::Devnet)
+        .unwrap();
+    assert_eq!(entry.source, OracleSource::File(path));
+    // Other clusters of an extended symbol keep their built-in entry
+    assert_eq!(
+        registry
+            .get("sol", Cluster::MainnetBeta)
+            .unwrap()
+            .to_string(),
+        "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG"
+    );
+
+    assert!(registry
+        .extend_from_str("[eth]\ndevnet = \"nope\"", OracleSource::BuiltIn)
+        .is_err());
+    // Cluster names are the ones `[program_ids]` accepts, `mainnet` is not one of them
+    let error = registry
+        .extend_from_str(
+            "[wif]\nmainnet = \"6ABgrEZk8urs6kJ1JNdC1sspH5zKXRqxy8sg3ZG2cQps\"",
+            OracleSource::BuiltIn,
+        )
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "wif.mainnet: Invalid cluster(mainnet), expected mainnet-beta, devnet, testnet or localnet"
+    );
+}
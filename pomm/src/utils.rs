@@ -1,22 +1,244 @@
-use crate::config::Config as PhoenixConfig;
+use crate::cluster::Cluster;
+use crate::config::{Config as PhoenixConfig, ConfigOverride, PhoenixOnChainMMConfig, Ticker};
+use crate::context::PommContext;
+use crate::oracle::OracleRegistry;
+use phoenix::program::MarketHeader;
+use phoenix_onchain_mm::constant::token_2022_program;
+use phoenix_onchain_mm::phoenix_v1::get_market_params;
+use phoenix_onchain_mm::price::{fair_price_in_ticks, FixedPrice};
 use phoenix_sdk::sdk_client::SDKClient;
+use pyth_sdk_solana::load_price_feed_from_account;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+use solana_program::keccak;
 use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::instruction::Instruction;
 use solana_sdk::program_pack::Pack;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signer;
 use spl_associated_token_account::instruction::create_associated_token_account;
 use spl_token::state::Mint;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn get_pomm_config() -> anyhow::Result<PhoenixConfig> {
-    let home_path = dirs::home_dir().ok_or(anyhow::anyhow!("can't open home dir"))?;
-    let pomm_config_path = home_path.join(".config").join("pomm");
-    let config_path = pomm_config_path.join("config.toml");
+/// The config file, profile and overrides chosen with the global `--config`, `--profile` and `--set` flags
+#[derive(Debug, Default, Clone)]
+pub struct ConfigSelection {
+    pub config_path: Option<PathBuf>,
+    pub profile: Option<String>,
+    /// `key=value` pairs applied over the config file and the `POMM_*` environment variables
+    pub overrides: Vec<String>,
+}
+
+/// Returns the `--config` path, then `POMM_CONFIG`, defaulting to `~/.config/pomm/config.toml`.
+pub fn get_config_path(selection: &ConfigSelection) -> anyhow::Result<PathBuf> {
+    let config_path = selection
+        .config_path
+        .clone()
+        .or_else(|| std::env::var_os("POMM_CONFIG").map(PathBuf::from));
+    match config_path {
+        Some(config_path) => Ok(config_path),
+        None => {
+            let home_path = dirs::home_dir().ok_or(anyhow::anyhow!("can't open home dir"))?;
+            Ok(home_path.join(".config").join("pomm").join("config.toml"))
+        }
+    }
+}
+
+/// The cluster of the RPC endpoint, with the program id and explorer links that depend on it.
+#[derive(Debug, Clone)]
+pub struct ClusterContext {
+    pub cluster: Cluster,
+    pub program_id: Pubkey,
+    pub explorer_url: Option<String>,
+    pub rpc_endpoint: String,
+}
+
+impl ClusterContext {
+    /// Returns the explorer link of a transaction.
+    pub fn explorer_tx_url(&self, signature: &impl std::fmt::Display) -> String {
+        let signature = signature.to_string();
+        match &self.explorer_url {
+            Some(explorer_url) => explorer_url.replace("{signature}", &signature),
+            None => self.cluster.explorer_tx_url(&signature, &self.rpc_endpoint),
+        }
+    }
+}
+
+/// Detects the cluster of `client` from its genesis hash.
+pub async fn detect_cluster(
+    phoneix_config: &PhoenixConfig,
+    client: &RpcClient,
+) -> anyhow::Result<ClusterContext> {
+    let cluster = Cluster::detect(client).await?;
+    Ok(ClusterContext {
+        cluster,
+        program_id: phoneix_config.get_program_id(cluster),
+        explorer_url: phoneix_config.explorer_url.clone(),
+        rpc_endpoint: client.url(),
+    })
+}
+
+/// Returns the oracle registry file, `oracles.toml` next to the config file.
+pub fn get_oracle_registry_path(selection: &ConfigSelection) -> anyhow::Result<PathBuf> {
+    Ok(get_config_path(selection)?.with_file_name("oracles.toml"))
+}
+
+/// Returns the built-in oracles extended by the registry file.
+pub fn get_oracle_registry(selection: &ConfigSelection) -> anyhow::Result<OracleRegistry> {
+    OracleRegistry::load(&get_oracle_registry_path(selection)?)
+}
+
+/// Loads the config file, then applies the `POMM_*` environment variables and the `--set` flags.
+pub fn get_pomm_config(selection: &ConfigSelection) -> anyhow::Result<PhoenixConfig> {
+    let config_path = get_config_path(selection)?;
+    let config_str = std::fs::read_to_string(&config_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read config {}: {}", config_path.display(), e))?;
+    let profile = selection
+        .profile
+        .clone()
+        .or_else(|| std::env::var("POMM_PROFILE").ok());
+    let mut overrides = ConfigOverride::from_env(std::env::vars());
+    for config_override in selection.overrides.iter() {
+        overrides.push(ConfigOverride::from_cli(config_override)?);
+    }
+    PhoenixConfig::from_layers(&config_str, profile.as_deref(), &overrides)
+}
 
-    let config_str = std::fs::read_to_string(config_path)?;
-    let phoneix_config: PhoenixConfig = toml::from_str(&config_str)?;
+/// Reads a Pyth price no older than a minute.
+pub async fn get_oracle_price(client: &RpcClient, oracle: &Pubkey) -> anyhow::Result<FixedPrice> {
+    let mut account = client.get_account(oracle).await?;
+    let price_feed = load_price_feed_from_account(oracle, &mut account)
+        .map_err(|e| anyhow::anyhow!("Failed to load price feed {}: {}", oracle, e))?;
+    let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let price = price_feed
+        .get_price_no_older_than(current_time, 60)
+        .ok_or(anyhow::anyhow!("price of {} is unavaiable", oracle))?;
+    Ok(FixedPrice::from_pyth(price.price, price.expo)?)
+}
+
+/// Loads the header of a Phoenix market.
+pub async fn get_market_header(
+    client: &RpcClient,
+    market: &Pubkey,
+) -> anyhow::Result<MarketHeader> {
+    let data = client.get_account_data(market).await?;
+    let header =
+        bytemuck::try_from_bytes::<MarketHeader>(&data[..std::mem::size_of::<MarketHeader>()])
+            .map_err(|_| anyhow::Error::msg("Failed to parse Phoenix market header"))?;
+    Ok(*header)
+}
+
+/// Returns the account discriminant Phoenix writes at the start of accounts of `type_name`.
+pub fn get_discriminant(type_name: &str) -> u64 {
+    u64::from_le_bytes(
+        keccak::hashv(&[phoenix::ID.as_ref(), type_name.as_bytes()]).as_ref()[..8]
+            .try_into()
+            .unwrap(),
+    )
+}
+
+/// Fetches the address and header of every Phoenix market.
+pub async fn get_all_markets(client: &RpcClient) -> anyhow::Result<Vec<(Pubkey, MarketHeader)>> {
+    let market_discriminant = get_discriminant("phoenix::program::accounts::MarketHeader");
+
+    // Memcmp encoding field is deprecated
+    #[allow(deprecated)]
+    let program_accounts = client
+        .get_program_accounts_with_config(
+            &phoenix::ID,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp {
+                    offset: 0,
+                    bytes: MemcmpEncodedBytes::Bytes(market_discriminant.to_le_bytes().to_vec()),
+                    encoding: None,
+                })]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..RpcAccountInfoConfig::default()
+                },
+
+                ..RpcProgramAccountsConfig::default()
+            },
+        )
+        .await?;
+
+    program_accounts
+        .into_iter()
+        .map(|(market, account)| {
+            // MarketHeader is fixed size, the market itself follows it
+            let header = bytemuck::try_from_bytes::<MarketHeader>(
+                &account.data[..std::mem::size_of::<MarketHeader>()],
+            )
+            .map_err(|_| anyhow::anyhow!("Failed to parse the header of market {}", market))?;
+            Ok((market, *header))
+        })
+        .collect()
+}
+
+/// Returns the markets trading the base token of `ticker`, its faucet mint or wrapped SOL.
+pub async fn get_market_addresses_by_ticker(
+    client: &RpcClient,
+    ticker: &Ticker,
+) -> anyhow::Result<Vec<Pubkey>> {
+    let markets = get_all_markets(client).await?;
+    println!("Found {} markets", markets.len());
+    Ok(markets
+        .into_iter()
+        .filter(|(_, header)| {
+            header.base_params.mint_key == generic_token_faucet::get_mint_address(&ticker.base)
+                || header.base_params.mint_key == spl_token::native_mint::id()
+        })
+        .map(|(market, _)| market)
+        .collect())
+}
 
-    Ok(phoneix_config)
+/// Loads the market and checks the config against its tick and lot sizes at the oracle price.
+pub async fn check_market_config(
+    context: &PommContext,
+    market_config: &PhoenixOnChainMMConfig,
+) -> anyhow::Result<()> {
+    let client = context.client();
+    let header = &context.get_market_header(&market_config.market).await?;
+    let base_price =
+        get_oracle_price(client, &context.get_base_oracle_account(market_config)?).await?;
+    let quote_price =
+        get_oracle_price(client, &context.get_quote_oracle_account(market_config)?).await?;
+    let fair_price = fair_price_in_ticks(base_price, quote_price, &get_market_params(header)?)?;
+    market_config.check_market(header, fair_price)
+}
+
+/// Returns the token program owning both market mints, the SPL token or the Token-2022 program.
+pub async fn get_token_program(
+    client: &RpcClient,
+    header: &MarketHeader,
+) -> anyhow::Result<Pubkey> {
+    let base_owner = client
+        .get_account(&header.base_params.mint_key)
+        .await?
+        .owner;
+    let quote_owner = client
+        .get_account(&header.quote_params.mint_key)
+        .await?
+        .owner;
+    if base_owner != quote_owner {
+        return Err(anyhow::anyhow!(
+            "Base mint is owned by {} but quote mint by {}",
+            base_owner,
+            quote_owner
+        ));
+    }
+    if base_owner != spl_token::id() && base_owner != token_2022_program::id() {
+        return Err(anyhow::anyhow!(
+            "Market mints are owned by {}, which is not a token program",
+            base_owner
+        ));
+    }
+    Ok(base_owner)
 }
 
 // Only needed for devnet testing
@@ -0,0 +1,656 @@
+//! Instruction builders for off-chain clients, enabled by the `client` feature.
+//!
+//! The plain builders target the program id the crate is built with and the associated token
+//! accounts of the trader. The `_with_program_id` and `_with_custom_accounts` variants cover
+//! other deployments, strategies created by another key and non-associated token accounts.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::InstructionData;
+use phoenix::program::{get_seat_address, get_vault_address, MarketHeader};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+use crate::constant::{token_program, ORACLE_SEED, STRATEGY_SEED};
+use crate::{
+    accounts, instruction, CircuitBreakerParams, OrderParams, ScheduleProfileParams, StrategyParams,
+};
+
+/// Token accounts the strategy trades from and the token program of the market mints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAccounts {
+    pub base_account: Pubkey,
+    pub quote_account: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl TokenAccounts {
+    /// The associated token accounts of `owner` for the market mints under `token_program`
+    pub fn associated(owner: &Pubkey, header: &MarketHeader, token_program: &Pubkey) -> Self {
+        TokenAccounts {
+            base_account: get_associated_token_address_with_program_id(
+                owner,
+                &header.base_params.mint_key,
+                token_program,
+            ),
+            quote_account: get_associated_token_address_with_program_id(
+                owner,
+                &header.quote_params.mint_key,
+                token_program,
+            ),
+            token_program: *token_program,
+        }
+    }
+}
+
+/// Returns the strategy `trader` creates on `market`
+pub fn get_strategy_address(trader: &Pubkey, market: &Pubkey) -> Pubkey {
+    get_strategy_address_with_program_id(&crate::id(), trader, market)
+}
+
+pub fn get_strategy_address_with_program_id(
+    program_id: &Pubkey,
+    trader: &Pubkey,
+    market: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[STRATEGY_SEED, trader.as_ref(), market.as_ref()],
+        program_id,
+    )
+    .0
+}
+
+/// Returns the oracle config of `strategy`
+pub fn get_oracle_config_address(strategy: &Pubkey) -> Pubkey {
+    get_oracle_config_address_with_program_id(&crate::id(), strategy)
+}
+
+pub fn get_oracle_config_address_with_program_id(program_id: &Pubkey, strategy: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[ORACLE_SEED, strategy.as_ref()], program_id).0
+}
+
+/// Creates the strategy of `trader` on `market`, `trader` signs and pays for it
+pub fn initialize_ix(trader: &Pubkey, market: &Pubkey, params: StrategyParams) -> Instruction {
+    initialize_ix_with_program_id(&crate::id(), trader, market, params)
+}
+
+pub fn initialize_ix_with_program_id(
+    program_id: &Pubkey,
+    trader: &Pubkey,
+    market: &Pubkey,
+    params: StrategyParams,
+) -> Instruction {
+    let phoenix_strategy = get_strategy_address_with_program_id(program_id, trader, market);
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::Initialize {
+            phoenix_strategy,
+            oracle_account: get_oracle_config_address_with_program_id(
+                program_id,
+                &phoenix_strategy,
+            ),
+            market: *market,
+            user: *trader,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::Initialize { params }.data(),
+    }
+}
+
+/// Upgrades the strategy `trader` created on `market` before the strategy roles existed,
+/// `trader` signs and pays for the larger account
+pub fn migrate_ix(trader: &Pubkey, market: &Pubkey) -> Instruction {
+    migrate_ix_with_program_id(&crate::id(), trader, market)
+}
+
+pub fn migrate_ix_with_program_id(
+    program_id: &Pubkey,
+    trader: &Pubkey,
+    market: &Pubkey,
+) -> Instruction {
+    let phoenix_strategy = get_strategy_address_with_program_id(program_id, trader, market);
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::Migrate {
+            phoenix_strategy,
+            legacy_oracle_account: Pubkey::find_program_address(
+                &[ORACLE_SEED, trader.as_ref(), market.as_ref()],
+                program_id,
+            )
+            .0,
+            oracle_account: get_oracle_config_address_with_program_id(
+                program_id,
+                &phoenix_strategy,
+            ),
+            user: *trader,
+            market: *market,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::Migrate {}.data(),
+    }
+}
+
+/// Quotes `market` with the strategy of `trader`, trading from its associated token accounts
+/// of the classic token program
+pub fn update_quotes_ix(
+    trader: &Pubkey,
+    market: &Pubkey,
+    header: &MarketHeader,
+    params: StrategyParams,
+) -> Instruction {
+    update_quotes_ix_with_custom_accounts(
+        &crate::id(),
+        &get_strategy_address(trader, market),
+        trader,
+        market,
+        header,
+        &TokenAccounts::associated(trader, header, &token_program::id()),
+        params,
+    )
+}
+
+/// Quotes `market` with `strategy`, `trader` must be the trader of the strategy
+pub fn update_quotes_ix_with_custom_accounts(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    trader: &Pubkey,
+    market: &Pubkey,
+    header: &MarketHeader,
+    token_accounts: &TokenAccounts,
+    params: StrategyParams,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::UpdateQuotes {
+            phoenix_strategy: *strategy,
+            oracle_account: get_oracle_config_address_with_program_id(program_id, strategy),
+            oracle_base_price: params.oracle_account_config.oracle_base_account,
+            oracle_quote_price: params.oracle_account_config.oracle_quote_account,
+            user: *trader,
+            phoenix_program: phoenix::id(),
+            log_authority: phoenix::phoenix_log_authority::id(),
+            market: *market,
+            seat: get_seat_address(market, trader).0,
+            quote_account: token_accounts.quote_account,
+            base_account: token_accounts.base_account,
+            quote_vault: get_vault_address(market, &header.quote_params.mint_key).0,
+            base_vault: get_vault_address(market, &header.base_params.mint_key).0,
+            base_mint: header.base_params.mint_key,
+            quote_mint: header.quote_params.mint_key,
+            token_program: token_accounts.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateQuotes {
+            params: OrderParams {
+                strategy_params: params,
+            },
+        }
+        .data(),
+    }
+}
+
+/// Simulates the quotes of `strategy` on `market` with `params`, `trader` must be the trader of
+/// the strategy but does not need to sign
+pub fn preview_quotes_ix(
+    strategy: &Pubkey,
+    trader: &Pubkey,
+    market: &Pubkey,
+    params: StrategyParams,
+) -> Instruction {
+    preview_quotes_ix_with_program_id(&crate::id(), strategy, trader, market, params)
+}
+
+pub fn preview_quotes_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    trader: &Pubkey,
+    market: &Pubkey,
+    params: StrategyParams,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::PreviewQuotes {
+            phoenix_strategy: *strategy,
+            oracle_account: get_oracle_config_address_with_program_id(program_id, strategy),
+            oracle_base_price: params.oracle_account_config.oracle_base_account,
+            oracle_quote_price: params.oracle_account_config.oracle_quote_account,
+            user: *trader,
+            market: *market,
+        }
+        .to_account_metas(None),
+        data: instruction::PreviewQuotes {
+            params: OrderParams {
+                strategy_params: params,
+            },
+        }
+        .data(),
+    }
+}
+
+/// Cancels every order of `trader` on `market` and stops `strategy` tracking its quotes
+pub fn cancel_all_ix(strategy: &Pubkey, trader: &Pubkey, market: &Pubkey) -> Instruction {
+    cancel_all_ix_with_program_id(&crate::id(), strategy, trader, market)
+}
+
+pub fn cancel_all_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    trader: &Pubkey,
+    market: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::CancelAll {
+            phoenix_strategy: *strategy,
+            user: *trader,
+            phoenix_program: phoenix::id(),
+            log_authority: phoenix::phoenix_log_authority::id(),
+            market: *market,
+        }
+        .to_account_metas(None),
+        data: instruction::CancelAll {}.data(),
+    }
+}
+
+/// Cancels the orders of `trader` on `market` that `strategy` does not track
+pub fn reconcile_ix(strategy: &Pubkey, trader: &Pubkey, market: &Pubkey) -> Instruction {
+    reconcile_ix_with_program_id(&crate::id(), strategy, trader, market)
+}
+
+pub fn reconcile_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    trader: &Pubkey,
+    market: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::Reconcile {
+            phoenix_strategy: *strategy,
+            user: *trader,
+            phoenix_program: phoenix::id(),
+            log_authority: phoenix::phoenix_log_authority::id(),
+            market: *market,
+        }
+        .to_account_metas(None),
+        data: instruction::Reconcile {}.data(),
+    }
+}
+
+/// Replaces the quoting windows of `strategy`, signed by its admin
+pub fn set_schedule_ix(
+    strategy: &Pubkey,
+    admin: &Pubkey,
+    profiles: Vec<ScheduleProfileParams>,
+) -> Instruction {
+    set_schedule_ix_with_program_id(&crate::id(), strategy, admin, profiles)
+}
+
+pub fn set_schedule_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    admin: &Pubkey,
+    profiles: Vec<ScheduleProfileParams>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::SetSchedule {
+            phoenix_strategy: *strategy,
+            admin: *admin,
+        }
+        .to_account_metas(None),
+        data: instruction::SetSchedule { profiles }.data(),
+    }
+}
+
+/// Stops `strategy` from quoting, signed by its guardian or admin
+pub fn halt_ix(strategy: &Pubkey, authority: &Pubkey) -> Instruction {
+    halt_ix_with_program_id(&crate::id(), strategy, authority)
+}
+
+pub fn halt_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::Halt {
+            phoenix_strategy: *strategy,
+            authority: *authority,
+        }
+        .to_account_metas(None),
+        data: instruction::Halt {}.data(),
+    }
+}
+
+/// Clears the tripped circuit breaker of `strategy`, signed by its admin
+pub fn reset_circuit_breaker_ix(strategy: &Pubkey, admin: &Pubkey) -> Instruction {
+    reset_circuit_breaker_ix_with_program_id(&crate::id(), strategy, admin)
+}
+
+pub fn reset_circuit_breaker_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    admin: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::ResetCircuitBreaker {
+            phoenix_strategy: *strategy,
+            admin: *admin,
+        }
+        .to_account_metas(None),
+        data: instruction::ResetCircuitBreaker {}.data(),
+    }
+}
+
+/// Sets the circuit breaker limits of `strategy`, signed by its admin
+pub fn set_circuit_breaker_ix(
+    strategy: &Pubkey,
+    admin: &Pubkey,
+    params: CircuitBreakerParams,
+) -> Instruction {
+    set_circuit_breaker_ix_with_program_id(&crate::id(), strategy, admin, params)
+}
+
+pub fn set_circuit_breaker_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    admin: &Pubkey,
+    params: CircuitBreakerParams,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::SetCircuitBreaker {
+            phoenix_strategy: *strategy,
+            admin: *admin,
+        }
+        .to_account_metas(None),
+        data: instruction::SetCircuitBreaker { params }.data(),
+    }
+}
+
+/// Hands the quoting of `strategy` to `new_trader`, signed by its admin
+pub fn set_trader_ix(strategy: &Pubkey, admin: &Pubkey, new_trader: &Pubkey) -> Instruction {
+    set_trader_ix_with_program_id(&crate::id(), strategy, admin, new_trader)
+}
+
+pub fn set_trader_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    admin: &Pubkey,
+    new_trader: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::SetTrader {
+            phoenix_strategy: *strategy,
+            admin: *admin,
+        }
+        .to_account_metas(None),
+        data: instruction::SetTrader {
+            new_trader: *new_trader,
+        }
+        .data(),
+    }
+}
+
+/// Sets the key allowed to halt `strategy`, signed by its admin
+pub fn set_guardian_ix(strategy: &Pubkey, admin: &Pubkey, new_guardian: &Pubkey) -> Instruction {
+    set_guardian_ix_with_program_id(&crate::id(), strategy, admin, new_guardian)
+}
+
+pub fn set_guardian_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    admin: &Pubkey,
+    new_guardian: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::SetGuardian {
+            phoenix_strategy: *strategy,
+            admin: *admin,
+        }
+        .to_account_metas(None),
+        data: instruction::SetGuardian {
+            new_guardian: *new_guardian,
+        }
+        .data(),
+    }
+}
+
+/// Starts handing `strategy` to `new_admin`, signed by its admin
+pub fn propose_admin_ix(strategy: &Pubkey, admin: &Pubkey, new_admin: &Pubkey) -> Instruction {
+    propose_admin_ix_with_program_id(&crate::id(), strategy, admin, new_admin)
+}
+
+pub fn propose_admin_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    admin: &Pubkey,
+    new_admin: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::ProposeAdmin {
+            phoenix_strategy: *strategy,
+            admin: *admin,
+        }
+        .to_account_metas(None),
+        data: instruction::ProposeAdmin {
+            new_admin: *new_admin,
+        }
+        .data(),
+    }
+}
+
+/// Completes the admin transfer of `strategy`, signed by the proposed admin
+pub fn accept_admin_ix(strategy: &Pubkey, pending_admin: &Pubkey) -> Instruction {
+    accept_admin_ix_with_program_id(&crate::id(), strategy, pending_admin)
+}
+
+pub fn accept_admin_ix_with_program_id(
+    program_id: &Pubkey,
+    strategy: &Pubkey,
+    pending_admin: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::AcceptAdmin {
+            phoenix_strategy: *strategy,
+            pending_admin: *pending_admin,
+        }
+        .to_account_metas(None),
+        data: instruction::AcceptAdmin {}.data(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracle::OracleConfig;
+    use anchor_lang::__private::bytemuck::Zeroable;
+    use anchor_lang::solana_program::instruction::AccountMeta;
+    use anchor_lang::Discriminator;
+
+    fn params() -> StrategyParams {
+        StrategyParams {
+            quote_edge_in_bps: Some(10),
+            quote_size_in_quote_atoms: Some(100_000_000),
+            taker_fee_edge_multiplier_in_bps: None,
+            hedge_cost_in_bps: None,
+            price_improvement_behavior: None,
+            post_only: Some(true),
+            reject_post_only: None,
+            self_trade_behavior: None,
+            cancel_untracked_orders: None,
+            fair_price_mode: None,
+            quote_model: None,
+            volatility_half_life_in_seconds: None,
+            min_slots_between_updates: None,
+            microprice_levels: None,
+            microprice_weight_in_bps: None,
+            oracle_account_config: OracleConfig {
+                oracle_base_account: Pubkey::new_unique(),
+                oracle_quote_account: Pubkey::new_unique(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_initialize_ix() {
+        let trader = Pubkey::new_unique();
+        let market = Pubkey::new_unique();
+        let ix = initialize_ix(&trader, &market, params());
+
+        let (strategy, _) = Pubkey::find_program_address(
+            &[b"phoenix", trader.as_ref(), market.as_ref()],
+            &crate::id(),
+        );
+        let (oracle_config, _) =
+            Pubkey::find_program_address(&[b"oracle", strategy.as_ref()], &crate::id());
+        assert_eq!(ix.program_id, crate::id());
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(strategy, false),
+                AccountMeta::new(oracle_config, false),
+                AccountMeta::new(trader, true),
+                AccountMeta::new_readonly(market, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ]
+        );
+        assert_eq!(ix.data[..8], instruction::Initialize::discriminator());
+    }
+
+    #[test]
+    fn test_update_quotes_ix() {
+        let trader = Pubkey::new_unique();
+        let market = Pubkey::new_unique();
+        let mut header: MarketHeader = Zeroable::zeroed();
+        header.base_params.mint_key = Pubkey::new_unique();
+        header.quote_params.mint_key = Pubkey::new_unique();
+        let params = params();
+        let ix = update_quotes_ix(&trader, &market, &header, params);
+
+        let strategy = get_strategy_address(&trader, &market);
+        let token_accounts = TokenAccounts::associated(&trader, &header, &token_program::id());
+        assert_eq!(ix.program_id, crate::id());
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(strategy, false),
+                AccountMeta::new_readonly(get_oracle_config_address(&strategy), false),
+                AccountMeta::new_readonly(params.oracle_account_config.oracle_base_account, false),
+                AccountMeta::new_readonly(params.oracle_account_config.oracle_quote_account, false),
+                AccountMeta::new_readonly(trader, true),
+                AccountMeta::new_readonly(phoenix::id(), false),
+                AccountMeta::new_readonly(phoenix::phoenix_log_authority::id(), false),
+                AccountMeta::new(market, false),
+                AccountMeta::new_readonly(get_seat_address(&market, &trader).0, false),
+                AccountMeta::new(token_accounts.quote_account, false),
+                AccountMeta::new(token_accounts.base_account, false),
+                AccountMeta::new(
+                    get_vault_address(&market, &header.quote_params.mint_key).0,
+                    false
+                ),
+                AccountMeta::new(
+                    get_vault_address(&market, &header.base_params.mint_key).0,
+                    false
+                ),
+                AccountMeta::new_readonly(header.base_params.mint_key, false),
+                AccountMeta::new_readonly(header.quote_params.mint_key, false),
+                AccountMeta::new_readonly(token_program::id(), false),
+            ]
+        );
+        assert_eq!(ix.data[..8], instruction::UpdateQuotes::discriminator());
+
+        // Another deployment trading from a Token-2022 treasury account
+        let program_id = Pubkey::new_unique();
+        let token_accounts = TokenAccounts {
+            base_account: Pubkey::new_unique(),
+            quote_account: Pubkey::new_unique(),
+            token_program: crate::constant::token_2022_program::id(),
+        };
+        let ix = update_quotes_ix_with_custom_accounts(
+            &program_id,
+            &strategy,
+            &trader,
+            &market,
+            &header,
+            &token_accounts,
+            params,
+        );
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(
+            ix.accounts[1].pubkey,
+            get_oracle_config_address_with_program_id(&program_id, &strategy)
+        );
+        assert_eq!(ix.accounts[9].pubkey, token_accounts.quote_account);
+        assert_eq!(ix.accounts[10].pubkey, token_accounts.base_account);
+        assert_eq!(ix.accounts[15].pubkey, token_accounts.token_program);
+    }
+
+    #[test]
+    fn test_strategy_ixs() {
+        let program_id = Pubkey::new_unique();
+        let strategy = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let new_key = Pubkey::new_unique();
+
+        let ix = set_trader_ix_with_program_id(&program_id, &strategy, &admin, &new_key);
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(strategy, false),
+                AccountMeta::new_readonly(admin, true),
+            ]
+        );
+        assert_eq!(ix.data[..8], instruction::SetTrader::discriminator());
+        assert_eq!(ix.data[8..], new_key.to_bytes());
+
+        let ix = halt_ix(&strategy, &admin);
+        assert_eq!(ix.program_id, crate::id());
+        assert_eq!(ix.data, instruction::Halt::discriminator());
+
+        let trader = Pubkey::new_unique();
+        let market = Pubkey::new_unique();
+        let ix = cancel_all_ix(&strategy, &trader, &market);
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(strategy, false),
+                AccountMeta::new_readonly(trader, true),
+                AccountMeta::new_readonly(phoenix::id(), false),
+                AccountMeta::new_readonly(phoenix::phoenix_log_authority::id(), false),
+                AccountMeta::new(market, false),
+            ]
+        );
+        assert_eq!(ix.data, instruction::CancelAll::discriminator());
+
+        // The preview reads the oracles of the given parameters, the trader does not sign
+        let params = params();
+        let ix =
+            preview_quotes_ix_with_program_id(&program_id, &strategy, &trader, &market, params);
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new_readonly(strategy, false),
+                AccountMeta::new_readonly(
+                    get_oracle_config_address_with_program_id(&program_id, &strategy),
+                    false
+                ),
+                AccountMeta::new_readonly(params.oracle_account_config.oracle_base_account, false),
+                AccountMeta::new_readonly(params.oracle_account_config.oracle_quote_account, false),
+                AccountMeta::new_readonly(trader, false),
+                AccountMeta::new_readonly(market, false),
+            ]
+        );
+        assert_eq!(ix.data[..8], instruction::PreviewQuotes::discriminator());
+    }
+}
@@ -1,3 +1,31 @@
 pub const PHOENIX_MARKET_DISCRIMINANT: u64 = 8167313896524341111;
-pub const BIG_NUMBER: u128 = 1_000_000_000_000;
-pub const BASE: u64 = 10;
+
+/// The classic SPL token program
+pub mod token_program {
+    anchor_lang::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+}
+
+/// The Token-2022 program
+pub mod token_2022_program {
+    anchor_lang::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+}
+
+/// The associated token account program
+pub mod associated_token_program {
+    anchor_lang::declare_id!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+}
+
+/// Seed of the strategy PDA, followed by the trader and the market
+pub const STRATEGY_SEED: &[u8] = b"phoenix";
+/// Seed of the oracle config PDA, followed by the strategy. Strategies created before the
+/// `migrate` instruction existed have theirs at this seed followed by the trader and the market.
+pub const ORACLE_SEED: &[u8] = b"oracle";
+/// Half-life of the volatility estimate when none is given at initialization
+pub const DEFAULT_VOLATILITY_HALF_LIFE_IN_SECONDS: u64 = 300;
+/// Number of price levels per side in the microprice when none is given at initialization
+pub const DEFAULT_MICROPRICE_LEVELS: u64 = 1;
+/// Number of schedule profile slots stored in the strategy
+pub const MAX_SCHEDULE_PROFILES: usize = 4;
+pub const MINUTES_PER_DAY: u16 = 24 * 60;
+/// Upper bound on the untracked orders cancelled by a single instruction
+pub const MAX_UNTRACKED_ORDERS_TO_CANCEL: usize = 16;
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::price::PriceError;
+
 // An enum for custom error codes
 #[error_code]
 pub enum StrategyError {
@@ -19,4 +21,35 @@ pub enum StrategyError {
     PythOffline,
     #[msg("Program should not try to serialize a price account.")]
     TryToSerializePriceAccount,
+    #[msg("signer is not allowed to perform this action")]
+    Unauthorized,
+    #[msg("price math overflowed")]
+    MathOverflow,
+    #[msg("price math divided by zero")]
+    DivisionByZero,
+    #[msg("oracle price is negative")]
+    NegativePrice,
+    #[msg("edge must be below 10000 bps")]
+    EdgeTooLarge,
+    #[msg("token program must be the SPL token or Token-2022 program owning the market mints")]
+    InvalidTokenProgram,
+    #[msg("strategy account is not in the layout that predates the strategy roles")]
+    NotLegacyStrategy,
+}
+
+impl From<PriceError> for StrategyError {
+    fn from(error: PriceError) -> Self {
+        match error {
+            PriceError::Overflow => StrategyError::MathOverflow,
+            PriceError::DivisionByZero => StrategyError::DivisionByZero,
+            PriceError::NegativePrice => StrategyError::NegativePrice,
+            PriceError::EdgeTooLarge => StrategyError::EdgeTooLarge,
+        }
+    }
+}
+
+impl From<PriceError> for anchor_lang::error::Error {
+    fn from(error: PriceError) -> Self {
+        StrategyError::from(error).into()
+    }
 }
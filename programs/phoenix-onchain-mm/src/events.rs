@@ -0,0 +1,12 @@
+use anchor_lang::prelude::*;
+
+/// Emitted by `update_quotes` when a new oracle return is folded into the volatility estimate
+#[event]
+pub struct VolatilityUpdated {
+    pub strategy: Pubkey,
+    pub oracle_price_in_ticks: u64,
+    /// Time since the previous oracle price the return is measured against
+    pub elapsed_seconds: u64,
+    pub return_variance_in_micro_bps_squared_per_second: u64,
+    pub unix_timestamp: i64,
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::StrategyError;
+use crate::state::PhoenixStrategyState;
+
+pub fn accept_admin_instruction(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let mut phoenix_strategy = ctx.accounts.phoenix_strategy.load_mut()?;
+    msg!(
+        "Admin transferred from {} to {}",
+        phoenix_strategy.admin,
+        phoenix_strategy.pending_admin
+    );
+    phoenix_strategy.admin = phoenix_strategy.pending_admin;
+    phoenix_strategy.pending_admin = Pubkey::default();
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        constraint = phoenix_strategy.load()?.pending_admin == pending_admin.key() @ StrategyError::Unauthorized,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    pub pending_admin: Signer<'info>,
+}
@@ -0,0 +1,53 @@
+use anchor_lang::{prelude::*, solana_program::program::invoke};
+
+use crate::errors::StrategyError;
+use crate::phoenix_v1::*;
+use crate::state::PhoenixStrategyState;
+
+/// Cancels every resting order of the trader and stops tracking the strategy quotes, so the
+/// next `update_quotes` does not count the cancelled orders as filled. Orders should be pulled
+/// through this instruction rather than with a Phoenix cancel sent directly by the trader.
+pub fn cancel_all_instruction(ctx: Context<CancelAll>) -> Result<()> {
+    let CancelAll {
+        phoenix_strategy,
+        user,
+        phoenix_program,
+        log_authority,
+        market,
+    } = ctx.accounts;
+
+    let mut phoenix_strategy = phoenix_strategy.load_mut()?;
+    phoenix_strategy.initial_bid_size_in_base_lots = 0;
+    phoenix_strategy.initial_ask_size_in_base_lots = 0;
+    msg!("Cancelling all orders");
+    invoke(
+        &phoenix::program::create_cancel_all_order_with_free_funds_instruction(
+            &market.key(),
+            &user.key(),
+        ),
+        &[
+            phoenix_program.to_account_info(),
+            log_authority.to_account_info(),
+            user.to_account_info(),
+            market.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelAll<'info> {
+    #[account(
+        mut,
+        constraint = phoenix_strategy.load()?.trader == user.key() @ StrategyError::Unauthorized,
+        constraint = phoenix_strategy.load()?.market == market.key() @ StrategyError::InvalidArgument,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    pub user: Signer<'info>,
+    pub phoenix_program: Program<'info, PhoenixV1>,
+    /// CHECK: Checked in CPI
+    pub log_authority: UncheckedAccount<'info>,
+    /// CHECK: Checked in CPI
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+}
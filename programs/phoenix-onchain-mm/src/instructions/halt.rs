@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::StrategyError;
+use crate::state::PhoenixStrategyState;
+
+/// Trips the circuit breaker by hand. Phoenix only lets the trader cancel its orders,
+/// so the resting orders are pulled by the next `update_quotes`.
+pub fn halt_instruction(ctx: Context<Halt>) -> Result<()> {
+    let mut phoenix_strategy = ctx.accounts.phoenix_strategy.load_mut()?;
+    msg!("Strategy halted by {}", ctx.accounts.authority.key());
+    phoenix_strategy.halted = true;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Halt<'info> {
+    #[account(
+        mut,
+        constraint = phoenix_strategy.load()?.is_guardian_or_admin(authority.key) @ StrategyError::Unauthorized,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    pub authority: Signer<'info>,
+}
@@ -1,11 +1,18 @@
 use anchor_lang::prelude::*;
 
+use crate::constant::{
+    DEFAULT_MICROPRICE_LEVELS, DEFAULT_VOLATILITY_HALF_LIFE_IN_SECONDS, MAX_SCHEDULE_PROFILES,
+    ORACLE_SEED, STRATEGY_SEED,
+};
 use crate::errors::StrategyError;
+use crate::instructions::FairPriceMode;
 use crate::instructions::PriceImprovementBehavior;
+use crate::instructions::QuoteModel;
+use crate::instructions::SelfTradeBehavior;
 use crate::instructions::StrategyParams;
 use crate::oracle::OracleConfig;
 use crate::phoenix_v1::load_header;
-use crate::state::PhoenixStrategyState;
+use crate::state::{PhoenixStrategyState, ScheduleProfile, NO_FILL_SIDE};
 
 pub fn initialize_instruction(ctx: Context<Initialize>, params: StrategyParams) -> Result<()> {
     require!(
@@ -18,6 +25,52 @@ pub fn initialize_instruction(ctx: Context<Initialize>, params: StrategyParams)
         params.quote_edge_in_bps.unwrap_or(0) > 0,
         StrategyError::EdgeMustBeNonZero
     );
+    require!(
+        params.quote_edge_in_bps.unwrap_or(0) < 10_000,
+        StrategyError::InvalidStrategyParams
+    );
+    let fair_price_mode = params.fair_price_mode.unwrap_or(FairPriceMode::Spot);
+    if let FairPriceMode::Blend { ema_weight_in_bps } = fair_price_mode {
+        require!(
+            ema_weight_in_bps <= 10_000,
+            StrategyError::InvalidStrategyParams
+        );
+    }
+    require!(
+        params.microprice_weight_in_bps.unwrap_or(0) <= 10_000,
+        StrategyError::InvalidStrategyParams
+    );
+    let microprice_levels = params
+        .microprice_levels
+        .unwrap_or(DEFAULT_MICROPRICE_LEVELS);
+    require!(microprice_levels > 0, StrategyError::InvalidStrategyParams);
+    let quote_model = params.quote_model.unwrap_or(QuoteModel::FixedEdge);
+    require!(quote_model.is_valid(), StrategyError::InvalidStrategyParams);
+    let (risk_aversion_ppm, order_arrival_intensity_ppm, horizon_in_seconds) = match quote_model {
+        QuoteModel::AvellanedaStoikov {
+            risk_aversion_ppm,
+            order_arrival_intensity_ppm,
+            horizon_in_seconds,
+        } => (
+            risk_aversion_ppm,
+            order_arrival_intensity_ppm,
+            horizon_in_seconds,
+        ),
+        QuoteModel::FixedEdge => (0, 0, 0),
+    };
+    let price_improvement_behavior = params
+        .price_improvement_behavior
+        .unwrap_or(PriceImprovementBehavior::Ignore);
+    let (penny_jump_max_ticks, penny_jump_min_edge_in_bps) = match price_improvement_behavior {
+        PriceImprovementBehavior::PennyJumpCapped {
+            max_ticks,
+            min_edge_bps,
+        } => {
+            require!(min_edge_bps < 10_000, StrategyError::InvalidStrategyParams);
+            (max_ticks, min_edge_bps)
+        }
+        _ => (0, 0),
+    };
     load_header(&ctx.accounts.market)?;
     let clock = Clock::get()?;
     msg!("Initializing Phoenix Strategy with params: {:?}", params);
@@ -25,6 +78,9 @@ pub fn initialize_instruction(ctx: Context<Initialize>, params: StrategyParams)
     *phoenix_strategy = PhoenixStrategyState {
         trader: *ctx.accounts.user.key,
         market: *ctx.accounts.market.key,
+        admin: *ctx.accounts.user.key,
+        pending_admin: Pubkey::default(),
+        guardian: *ctx.accounts.user.key,
         bid_order_sequence_number: 0,
         bid_price_in_ticks: 0,
         initial_bid_size_in_base_lots: 0,
@@ -33,14 +89,52 @@ pub fn initialize_instruction(ctx: Context<Initialize>, params: StrategyParams)
         initial_ask_size_in_base_lots: 0,
         last_update_slot: clock.slot,
         last_update_unix_timestamp: clock.unix_timestamp,
+        min_slots_between_updates: params.min_slots_between_updates.unwrap_or(0),
         quote_edge_in_bps: params.quote_edge_in_bps.unwrap_or(0),
         quote_size_in_quote_atoms: params.quote_size_in_quote_atoms.unwrap_or(0),
+        taker_fee_edge_multiplier_in_bps: params.taker_fee_edge_multiplier_in_bps.unwrap_or(0),
+        hedge_cost_in_bps: params.hedge_cost_in_bps.unwrap_or(0),
         post_only: params.post_only.unwrap_or(false),
-        price_improvement_behavior: params
-            .price_improvement_behavior
-            .unwrap_or(PriceImprovementBehavior::Ignore)
+        price_improvement_behavior: price_improvement_behavior.to_u8(),
+        halted: false,
+        last_fill_side: NO_FILL_SIDE,
+        fair_price_mode: fair_price_mode.to_u8(),
+        reject_post_only: params.reject_post_only.unwrap_or(false),
+        self_trade_behavior: params
+            .self_trade_behavior
+            .unwrap_or(SelfTradeBehavior::CancelProvide)
             .to_u8(),
-        padding: [0; 6],
+        cancel_untracked_orders: params.cancel_untracked_orders.unwrap_or(false),
+        quote_model: quote_model.to_u8(),
+        padding: [0; 7],
+        ema_weight_in_bps: match fair_price_mode {
+            FairPriceMode::Blend { ema_weight_in_bps } => ema_weight_in_bps,
+            _ => 0,
+        },
+        penny_jump_max_ticks,
+        penny_jump_min_edge_in_bps,
+        microprice_levels,
+        microprice_weight_in_bps: params.microprice_weight_in_bps.unwrap_or(0),
+        risk_aversion_ppm,
+        order_arrival_intensity_ppm,
+        horizon_in_seconds,
+        volatility_half_life_in_seconds: params
+            .volatility_half_life_in_seconds
+            .unwrap_or(DEFAULT_VOLATILITY_HALF_LIFE_IN_SECONDS),
+        return_variance_in_micro_bps_squared_per_second: 0,
+        last_oracle_price_in_ticks: 0,
+        last_oracle_unix_timestamp: 0,
+        // Set by the admin with `set_circuit_breaker`
+        max_base_lots_filled_per_window: 0,
+        fill_window_in_slots: 0,
+        max_loss_in_quote_atoms: 0,
+        max_consecutive_one_sided_fills: 0,
+        fill_window_start_slot: clock.slot,
+        base_lots_filled_in_window: 0,
+        base_lots_position: 0,
+        quote_atoms_position: 0,
+        consecutive_one_sided_fills: 0,
+        schedule_profiles: [ScheduleProfile::default(); MAX_SCHEDULE_PROFILES],
     };
     ctx.accounts
         .oracle_account
@@ -52,7 +146,7 @@ pub fn initialize_instruction(ctx: Context<Initialize>, params: StrategyParams)
 pub struct Initialize<'info> {
     #[account(
         init,
-        seeds=[b"phoenix".as_ref(), user.key.as_ref(), market.key.as_ref()],
+        seeds=[STRATEGY_SEED, user.key.as_ref(), market.key.as_ref()],
         bump,
         payer = user,
         space = 8 + std::mem::size_of::<PhoenixStrategyState>(),
@@ -62,7 +156,7 @@ pub struct Initialize<'info> {
          init,
          payer = user,
          space = 8 + OracleConfig::LEN,
-         seeds = [b"oracle", user.key.as_ref(), market.key.as_ref()],
+         seeds = [ORACLE_SEED, phoenix_strategy.key().as_ref()],
          bump
      )]
     pub oracle_account: Account<'info, OracleConfig>,
@@ -0,0 +1,155 @@
+use anchor_lang::__private::bytemuck;
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_lang::Discriminator;
+
+use crate::constant::{
+    DEFAULT_MICROPRICE_LEVELS, DEFAULT_VOLATILITY_HALF_LIFE_IN_SECONDS, MAX_SCHEDULE_PROFILES,
+    ORACLE_SEED, STRATEGY_SEED,
+};
+use crate::errors::StrategyError;
+use crate::instructions::{FairPriceMode, QuoteModel, SelfTradeBehavior};
+use crate::oracle::OracleConfig;
+use crate::state::{
+    LegacyPhoenixStrategyState, PhoenixStrategyState, ScheduleProfile, NO_FILL_SIDE,
+};
+
+/// Upgrades a strategy created before the admin, trader and guardian roles were split. The
+/// account is grown to the current layout, with the trader in every role and the settings added
+/// since at their `initialize` defaults, and the oracle config moves to the PDA of the strategy.
+pub fn migrate_instruction(ctx: Context<Migrate>) -> Result<()> {
+    let Migrate {
+        phoenix_strategy,
+        legacy_oracle_account,
+        oracle_account,
+        user,
+        market,
+        system_program,
+    } = ctx.accounts;
+
+    require_keys_eq!(
+        *phoenix_strategy.owner,
+        crate::id(),
+        StrategyError::InvalidArgument
+    );
+    let legacy = {
+        let data = phoenix_strategy.try_borrow_data()?;
+        require!(
+            data.len() == 8 + LegacyPhoenixStrategyState::LEN
+                && data[..8] == PhoenixStrategyState::discriminator(),
+            StrategyError::NotLegacyStrategy
+        );
+        LegacyPhoenixStrategyState::try_from_slice(&data[8..])?
+    };
+    require_keys_eq!(legacy.trader, user.key(), StrategyError::Unauthorized);
+    require_keys_eq!(legacy.market, market.key(), StrategyError::InvalidArgument);
+
+    // Top up the rent before growing the account
+    let new_len = 8 + std::mem::size_of::<PhoenixStrategyState>();
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(phoenix_strategy.lamports());
+    if rent_due > 0 {
+        transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: user.to_account_info(),
+                    to: phoenix_strategy.to_account_info(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    phoenix_strategy.realloc(new_len, true)?;
+
+    let clock = Clock::get()?;
+    let strategy = PhoenixStrategyState {
+        trader: legacy.trader,
+        market: legacy.market,
+        admin: legacy.trader,
+        pending_admin: Pubkey::default(),
+        guardian: legacy.trader,
+        bid_order_sequence_number: legacy.bid_order_sequence_number,
+        bid_price_in_ticks: legacy.bid_price_in_ticks,
+        initial_bid_size_in_base_lots: legacy.initial_bid_size_in_base_lots,
+        ask_order_sequence_number: legacy.ask_order_sequence_number,
+        ask_price_in_ticks: legacy.ask_price_in_ticks,
+        initial_ask_size_in_base_lots: legacy.initial_ask_size_in_base_lots,
+        last_update_slot: legacy.last_update_slot,
+        last_update_unix_timestamp: legacy.last_update_unix_timestamp,
+        min_slots_between_updates: 0,
+        quote_edge_in_bps: legacy.quote_edge_in_bps,
+        quote_size_in_quote_atoms: legacy.quote_size_in_quote_atoms,
+        taker_fee_edge_multiplier_in_bps: 0,
+        hedge_cost_in_bps: 0,
+        post_only: legacy.post_only,
+        price_improvement_behavior: legacy.price_improvement_behavior,
+        halted: false,
+        last_fill_side: NO_FILL_SIDE,
+        fair_price_mode: FairPriceMode::Spot.to_u8(),
+        reject_post_only: false,
+        self_trade_behavior: SelfTradeBehavior::CancelProvide.to_u8(),
+        cancel_untracked_orders: false,
+        quote_model: QuoteModel::FixedEdge.to_u8(),
+        padding: [0; 7],
+        ema_weight_in_bps: 0,
+        penny_jump_max_ticks: 0,
+        penny_jump_min_edge_in_bps: 0,
+        microprice_levels: DEFAULT_MICROPRICE_LEVELS,
+        microprice_weight_in_bps: 0,
+        risk_aversion_ppm: 0,
+        order_arrival_intensity_ppm: 0,
+        horizon_in_seconds: 0,
+        volatility_half_life_in_seconds: DEFAULT_VOLATILITY_HALF_LIFE_IN_SECONDS,
+        return_variance_in_micro_bps_squared_per_second: 0,
+        last_oracle_price_in_ticks: 0,
+        last_oracle_unix_timestamp: 0,
+        max_base_lots_filled_per_window: 0,
+        fill_window_in_slots: 0,
+        max_loss_in_quote_atoms: 0,
+        max_consecutive_one_sided_fills: 0,
+        fill_window_start_slot: clock.slot,
+        base_lots_filled_in_window: 0,
+        base_lots_position: 0,
+        quote_atoms_position: 0,
+        consecutive_one_sided_fills: 0,
+        schedule_profiles: [ScheduleProfile::default(); MAX_SCHEDULE_PROFILES],
+    };
+    phoenix_strategy.try_borrow_mut_data()?[8..].copy_from_slice(bytemuck::bytes_of(&strategy));
+
+    oracle_account.set_inner(**legacy_oracle_account);
+    msg!("Migrated strategy {}", phoenix_strategy.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    /// CHECK: Owner and legacy layout checked in instruction
+    #[account(
+        mut,
+        seeds = [STRATEGY_SEED, user.key.as_ref(), market.key.as_ref()],
+        bump,
+    )]
+    pub phoenix_strategy: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [ORACLE_SEED, user.key.as_ref(), market.key.as_ref()],
+        bump,
+    )]
+    pub legacy_oracle_account: Account<'info, OracleConfig>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + OracleConfig::LEN,
+        seeds = [ORACLE_SEED, phoenix_strategy.key().as_ref()],
+        bump,
+    )]
+    pub oracle_account: Account<'info, OracleConfig>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Checked against the strategy in instruction
+    pub market: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -1,9 +1,33 @@
 use anchor_lang::prelude::*;
 
+mod accept_admin;
+mod cancel_all;
+mod halt;
 mod initialize;
+mod migrate;
+mod preview_quotes;
+mod propose_admin;
+mod reconcile;
+mod reset_circuit_breaker;
+mod set_circuit_breaker;
+mod set_guardian;
+mod set_schedule;
+mod set_trader;
 mod update_quotes;
 
+pub use accept_admin::*;
+pub use cancel_all::*;
+pub use halt::*;
 pub use initialize::*;
+pub use migrate::*;
+pub use preview_quotes::*;
+pub use propose_admin::*;
+pub use reconcile::*;
+pub use reset_circuit_breaker::*;
+pub use set_circuit_breaker::*;
+pub use set_guardian::*;
+pub use set_schedule::*;
+pub use set_trader::*;
 pub use update_quotes::*;
 
 use crate::oracle::OracleConfig;
@@ -13,23 +37,155 @@ pub enum PriceImprovementBehavior {
     Join,
     Dime,
     Ignore,
+    /// Step `max_ticks` ahead of the best competing order, but never closer than `min_edge_bps` to fair value
+    PennyJumpCapped {
+        max_ticks: u64,
+        min_edge_bps: u64,
+    },
 }
-
 impl PriceImprovementBehavior {
     pub fn to_u8(&self) -> u8 {
         match self {
             PriceImprovementBehavior::Join => 0,
             PriceImprovementBehavior::Dime => 1,
             PriceImprovementBehavior::Ignore => 2,
+            PriceImprovementBehavior::PennyJumpCapped { .. } => 3,
+        }
+    }
+
+    pub fn from_u8(
+        byte: u8,
+        penny_jump_max_ticks: u64,
+        penny_jump_min_edge_in_bps: u64,
+    ) -> Option<Self> {
+        match byte {
+            0 => Some(PriceImprovementBehavior::Join),
+            1 => Some(PriceImprovementBehavior::Dime),
+            2 => Some(PriceImprovementBehavior::Ignore),
+            3 => Some(PriceImprovementBehavior::PennyJumpCapped {
+                max_ticks: penny_jump_max_ticks,
+                min_edge_bps: penny_jump_min_edge_in_bps,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Phoenix self-trade behavior used for limit orders, post-only orders never take liquidity
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum SelfTradeBehavior {
+    /// Fail the order if it would match against one of our orders
+    Abort,
+    /// Cancel our resting order and keep matching, this is the Phoenix default
+    CancelProvide,
+    /// Reduce both orders by the overlapping size without a trade
+    DecrementTake,
+}
+
+impl SelfTradeBehavior {
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            SelfTradeBehavior::Abort => 0,
+            SelfTradeBehavior::CancelProvide => 1,
+            SelfTradeBehavior::DecrementTake => 2,
         }
     }
 
-    pub fn from_u8(byte: u8) -> Self {
+    pub fn from_u8(byte: u8) -> Option<Self> {
         match byte {
-            0 => PriceImprovementBehavior::Join,
-            1 => PriceImprovementBehavior::Dime,
-            2 => PriceImprovementBehavior::Ignore,
-            _ => panic!("Invalid PriceImprovementBehavior"),
+            0 => Some(SelfTradeBehavior::Abort),
+            1 => Some(SelfTradeBehavior::CancelProvide),
+            2 => Some(SelfTradeBehavior::DecrementTake),
+            _ => None,
+        }
+    }
+
+    pub fn to_phoenix(&self) -> phoenix::state::SelfTradeBehavior {
+        match self {
+            SelfTradeBehavior::Abort => phoenix::state::SelfTradeBehavior::Abort,
+            SelfTradeBehavior::CancelProvide => phoenix::state::SelfTradeBehavior::CancelProvide,
+            SelfTradeBehavior::DecrementTake => phoenix::state::SelfTradeBehavior::DecrementTake,
+        }
+    }
+}
+
+/// Which Pyth price the fair value is derived from. The same mode is used for the base and quote legs.
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum FairPriceMode {
+    Spot,
+    Ema,
+    /// `ema_weight_in_bps` of the EMA price and the rest of the spot price
+    Blend {
+        ema_weight_in_bps: u64,
+    },
+}
+
+impl FairPriceMode {
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            FairPriceMode::Spot => 0,
+            FairPriceMode::Ema => 1,
+            FairPriceMode::Blend { .. } => 2,
+        }
+    }
+
+    pub fn from_u8(byte: u8, ema_weight_in_bps: u64) -> Option<Self> {
+        match byte {
+            0 => Some(FairPriceMode::Spot),
+            1 => Some(FairPriceMode::Ema),
+            2 => Some(FairPriceMode::Blend { ema_weight_in_bps }),
+            _ => None,
+        }
+    }
+}
+
+/// How the bid and ask are placed around the fair price
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum QuoteModel {
+    /// `quote_edge_in_bps` on either side of the fair price
+    FixedEdge,
+    /// Avellaneda–Stoikov quotes skewed by the inventory since the last circuit breaker reset,
+    /// with `quote_edge_in_bps` as the minimum half spread. See `price::AvellanedaStoikovParams`.
+    AvellanedaStoikov {
+        risk_aversion_ppm: u64,
+        order_arrival_intensity_ppm: u64,
+        horizon_in_seconds: u64,
+    },
+}
+
+impl QuoteModel {
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            QuoteModel::FixedEdge => 0,
+            QuoteModel::AvellanedaStoikov { .. } => 1,
+        }
+    }
+
+    pub fn from_u8(
+        byte: u8,
+        risk_aversion_ppm: u64,
+        order_arrival_intensity_ppm: u64,
+        horizon_in_seconds: u64,
+    ) -> Option<Self> {
+        match byte {
+            0 => Some(QuoteModel::FixedEdge),
+            1 => Some(QuoteModel::AvellanedaStoikov {
+                risk_aversion_ppm,
+                order_arrival_intensity_ppm,
+                horizon_in_seconds,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            QuoteModel::FixedEdge => true,
+            QuoteModel::AvellanedaStoikov {
+                risk_aversion_ppm,
+                order_arrival_intensity_ppm,
+                ..
+            } => *risk_aversion_ppm > 0 && *order_arrival_intensity_ppm > 0,
         }
     }
 }
@@ -43,7 +199,44 @@ pub struct OrderParams {
 pub struct StrategyParams {
     pub quote_edge_in_bps: Option<u64>,
     pub quote_size_in_quote_atoms: Option<u64>,
+    /// Floors the edge at this multiple (in bps, 10_000 is 1x) of the market taker fee plus `hedge_cost_in_bps`
+    pub taker_fee_edge_multiplier_in_bps: Option<u64>,
+    pub hedge_cost_in_bps: Option<u64>,
     pub price_improvement_behavior: Option<PriceImprovementBehavior>,
     pub post_only: Option<bool>,
+    /// Reject post-only orders that would cross instead of sliding them to the best non-crossing price
+    pub reject_post_only: Option<bool>,
+    pub self_trade_behavior: Option<SelfTradeBehavior>,
+    /// Cancel resting orders of the trader that the strategy does not track on every update
+    pub cancel_untracked_orders: Option<bool>,
+    pub fair_price_mode: Option<FairPriceMode>,
+    pub quote_model: Option<QuoteModel>,
+    /// Half-life of the volatility estimate, 0 keeps only the latest return
+    pub volatility_half_life_in_seconds: Option<u64>,
+    pub min_slots_between_updates: Option<u64>,
+    /// Number of price levels per side used for the microprice, at least 1
+    pub microprice_levels: Option<u64>,
+    /// Weight of the book microprice against the oracle price, 0 disables it
+    pub microprice_weight_in_bps: Option<u64>,
     pub oracle_account_config: OracleConfig,
 }
+
+/// The quotes the strategy would place for the current oracle price and book,
+/// returned by `preview_quotes` as return data.
+#[derive(Debug, AnchorDeserialize, AnchorSerialize, Clone, Copy)]
+pub struct QuotePreview {
+    pub fair_price_in_ticks: u64,
+    pub oracle_price_in_ticks: u64,
+    /// 0 when the microprice is disabled or one side of the book is empty
+    pub microprice_in_ticks: u64,
+    pub edge_in_bps: u64,
+    /// Volatility estimate as of the last update, see `PhoenixStrategyState`
+    pub return_variance_in_micro_bps_squared_per_second: u64,
+    /// Best prices of the other traders, 0 when that side of the book is empty
+    pub best_bid_in_ticks: u64,
+    pub best_ask_in_ticks: u64,
+    pub bid_price_in_ticks: u64,
+    pub bid_size_in_base_lots: u64,
+    pub ask_price_in_ticks: u64,
+    pub ask_size_in_base_lots: u64,
+}
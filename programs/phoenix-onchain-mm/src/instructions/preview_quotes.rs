@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+use phoenix::program::MarketHeader;
+
+use super::{apply_strategy_params, compute_quotes, OrderParams, QuotePreview};
+use crate::constant::ORACLE_SEED;
+use crate::errors::StrategyError;
+use crate::oracle::{OracleConfig, PriceFeed};
+use crate::phoenix_v1::*;
+use crate::state::PhoenixStrategyState;
+
+pub fn preview_quotes_instruction(
+    ctx: Context<PreviewQuotes>,
+    params: OrderParams,
+) -> Result<QuotePreview> {
+    let PreviewQuotes {
+        phoenix_strategy,
+        user,
+        market: market_account,
+        oracle_base_price,
+        oracle_quote_price,
+        ..
+    } = ctx.accounts;
+
+    // Work on a copy so the preview never changes the stored strategy
+    let mut phoenix_strategy = *phoenix_strategy.load()?;
+    apply_strategy_params(&mut phoenix_strategy, &params.strategy_params)?;
+
+    let clock = Clock::get()?;
+    let header = load_header(market_account)?;
+    let market_data = market_account.data.borrow();
+    let (_, market_bytes) = market_data.split_at(std::mem::size_of::<MarketHeader>());
+    let market = load_market(&header, market_bytes)?;
+
+    compute_quotes(
+        &phoenix_strategy,
+        &header,
+        market,
+        &user.key(),
+        oracle_base_price,
+        oracle_quote_price,
+        &clock,
+    )
+}
+
+#[derive(Accounts)]
+pub struct PreviewQuotes<'info> {
+    #[account(
+        constraint = phoenix_strategy.load()?.trader == user.key() @ StrategyError::Unauthorized,
+        constraint = phoenix_strategy.load()?.market == market.key() @ StrategyError::InvalidArgument,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    #[account(
+            seeds = [ORACLE_SEED, phoenix_strategy.key().as_ref()],
+            bump
+    )]
+    pub oracle_account: Account<'info, OracleConfig>,
+    #[account(
+        address = oracle_account.oracle_base_account @ StrategyError::InvalidArgument
+    )]
+    pub oracle_base_price: Account<'info, PriceFeed>,
+    #[account(
+        address = oracle_account.oracle_quote_account @ StrategyError::InvalidArgument
+    )]
+    pub oracle_quote_price: Account<'info, PriceFeed>,
+    /// CHECK: Must be the strategy trader, the preview does not need a signature
+    pub user: UncheckedAccount<'info>,
+    /// CHECK: Checked in instruction
+    pub market: UncheckedAccount<'info>,
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::StrategyError;
+use crate::state::PhoenixStrategyState;
+
+/// First step of an admin transfer, the new admin has to accept it with `accept_admin`.
+/// Proposing `Pubkey::default()` cancels a pending transfer.
+pub fn propose_admin_instruction(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    let mut phoenix_strategy = ctx.accounts.phoenix_strategy.load_mut()?;
+    msg!("Proposing {} as the new admin", new_admin);
+    phoenix_strategy.pending_admin = new_admin;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    #[account(
+        mut,
+        constraint = phoenix_strategy.load()?.admin == admin.key() @ StrategyError::Unauthorized,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    pub admin: Signer<'info>,
+}
@@ -0,0 +1,76 @@
+use anchor_lang::{prelude::*, solana_program::program::invoke};
+use phoenix::program::MarketHeader;
+use phoenix::state::markets::FIFOOrderId;
+
+use crate::errors::StrategyError;
+use crate::phoenix_v1::*;
+use crate::state::PhoenixStrategyState;
+
+/// Cancels the resting orders of the trader that the strategy does not track,
+/// without touching the strategy quotes.
+pub fn reconcile_instruction(ctx: Context<Reconcile>) -> Result<()> {
+    let Reconcile {
+        phoenix_strategy,
+        user,
+        phoenix_program,
+        log_authority,
+        market: market_account,
+    } = ctx.accounts;
+
+    let phoenix_strategy = phoenix_strategy.load()?;
+    let tracked_order_ids = [
+        FIFOOrderId::new_from_untyped(
+            phoenix_strategy.bid_price_in_ticks,
+            phoenix_strategy.bid_order_sequence_number,
+        ),
+        FIFOOrderId::new_from_untyped(
+            phoenix_strategy.ask_price_in_ticks,
+            phoenix_strategy.ask_order_sequence_number,
+        ),
+    ];
+
+    let header = load_header(market_account)?;
+    let market_data = market_account.data.borrow();
+    let (_, market_bytes) = market_data.split_at(std::mem::size_of::<MarketHeader>());
+    let market = load_market(&header, market_bytes)?;
+    let untracked_order_ids = get_untracked_order_ids(market, &user.key(), &tracked_order_ids);
+
+    // Drop reference prior to invoking
+    drop(market_data);
+
+    if untracked_order_ids.is_empty() {
+        msg!("No untracked orders");
+        return Ok(());
+    }
+    msg!("Cancelling {} untracked orders", untracked_order_ids.len());
+    invoke(
+        &phoenix::program::create_cancel_multiple_orders_by_id_with_free_funds_instruction(
+            &market_account.key(),
+            &user.key(),
+            &get_cancel_params(&untracked_order_ids),
+        ),
+        &[
+            phoenix_program.to_account_info(),
+            log_authority.to_account_info(),
+            user.to_account_info(),
+            market_account.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Reconcile<'info> {
+    #[account(
+        constraint = phoenix_strategy.load()?.trader == user.key() @ StrategyError::Unauthorized,
+        constraint = phoenix_strategy.load()?.market == market.key() @ StrategyError::InvalidArgument,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    pub user: Signer<'info>,
+    pub phoenix_program: Program<'info, PhoenixV1>,
+    /// CHECK: Checked in CPI
+    pub log_authority: UncheckedAccount<'info>,
+    /// CHECK: Checked in instruction and CPI
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+}
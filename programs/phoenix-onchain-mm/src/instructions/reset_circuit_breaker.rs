@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::StrategyError;
+use crate::state::PhoenixStrategyState;
+
+pub fn reset_circuit_breaker_instruction(ctx: Context<ResetCircuitBreaker>) -> Result<()> {
+    let mut phoenix_strategy = ctx.accounts.phoenix_strategy.load_mut()?;
+    msg!(
+        "Resetting circuit breaker, halted = {}, base lots position = {}, quote atoms position = {}",
+        phoenix_strategy.halted,
+        phoenix_strategy.base_lots_position,
+        phoenix_strategy.quote_atoms_position
+    );
+    phoenix_strategy.reset_circuit_breaker(Clock::get()?.slot);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ResetCircuitBreaker<'info> {
+    #[account(
+        mut,
+        constraint = phoenix_strategy.load()?.admin == admin.key() @ StrategyError::Unauthorized,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    pub admin: Signer<'info>,
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::StrategyError;
+use crate::state::PhoenixStrategyState;
+
+/// Circuit breaker limits, 0 disables a check
+#[derive(Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub struct CircuitBreakerParams {
+    /// Base lots filled on both sides within `fill_window_in_slots`
+    pub max_base_lots_filled_per_window: u64,
+    pub fill_window_in_slots: u64,
+    /// Loss of the position since the last reset, marked at the spot oracle price
+    pub max_loss_in_quote_atoms: u64,
+    pub max_consecutive_one_sided_fills: u64,
+}
+
+/// Replaces the circuit breaker limits. Only the admin sets them, the trader that signs the
+/// quote updates cannot loosen the limits that guard it.
+pub fn set_circuit_breaker_instruction(
+    ctx: Context<SetCircuitBreaker>,
+    params: CircuitBreakerParams,
+) -> Result<()> {
+    let mut phoenix_strategy = ctx.accounts.phoenix_strategy.load_mut()?;
+    msg!("Setting circuit breaker limits: {:?}", params);
+    phoenix_strategy.max_base_lots_filled_per_window = params.max_base_lots_filled_per_window;
+    phoenix_strategy.fill_window_in_slots = params.fill_window_in_slots;
+    phoenix_strategy.max_loss_in_quote_atoms = params.max_loss_in_quote_atoms;
+    phoenix_strategy.max_consecutive_one_sided_fills = params.max_consecutive_one_sided_fills;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetCircuitBreaker<'info> {
+    #[account(
+        mut,
+        constraint = phoenix_strategy.load()?.admin == admin.key() @ StrategyError::Unauthorized,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    pub admin: Signer<'info>,
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::StrategyError;
+use crate::state::PhoenixStrategyState;
+
+/// Setting the guardian to `Pubkey::default()` leaves only the admin able to halt the strategy.
+pub fn set_guardian_instruction(ctx: Context<SetGuardian>, new_guardian: Pubkey) -> Result<()> {
+    let mut phoenix_strategy = ctx.accounts.phoenix_strategy.load_mut()?;
+    msg!(
+        "Guardian changed from {} to {}",
+        phoenix_strategy.guardian,
+        new_guardian
+    );
+    phoenix_strategy.guardian = new_guardian;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    #[account(
+        mut,
+        constraint = phoenix_strategy.load()?.admin == admin.key() @ StrategyError::Unauthorized,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    pub admin: Signer<'info>,
+}
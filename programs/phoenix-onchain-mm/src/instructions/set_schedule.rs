@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::constant::MAX_SCHEDULE_PROFILES;
+use crate::errors::StrategyError;
+use crate::state::{PhoenixStrategyState, ScheduleProfile};
+
+#[derive(Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub struct ScheduleProfileParams {
+    pub day_mask: u8,
+    pub start_minute_of_day: u16,
+    pub end_minute_of_day: u16,
+    pub quote_edge_in_bps: u64,
+    pub quote_size_in_quote_atoms: u64,
+}
+
+/// Replaces the whole schedule, the profiles are checked in the given order.
+/// An empty list clears the schedule. Only the admin sets it, the scheduled edges and sizes
+/// take precedence over the ones the trader passes with its quote updates.
+pub fn set_schedule_instruction(
+    ctx: Context<SetSchedule>,
+    profiles: Vec<ScheduleProfileParams>,
+) -> Result<()> {
+    require!(
+        profiles.len() <= MAX_SCHEDULE_PROFILES,
+        StrategyError::InvalidStrategyParams
+    );
+    let mut schedule_profiles = [ScheduleProfile::default(); MAX_SCHEDULE_PROFILES];
+    for (slot, params) in schedule_profiles.iter_mut().zip(profiles.iter()) {
+        *slot = ScheduleProfile {
+            day_mask: params.day_mask,
+            start_minute_of_day: params.start_minute_of_day,
+            end_minute_of_day: params.end_minute_of_day,
+            quote_edge_in_bps: params.quote_edge_in_bps,
+            quote_size_in_quote_atoms: params.quote_size_in_quote_atoms,
+            ..Default::default()
+        };
+        require!(slot.is_valid(), StrategyError::InvalidStrategyParams);
+    }
+
+    let mut phoenix_strategy = ctx.accounts.phoenix_strategy.load_mut()?;
+    phoenix_strategy.schedule_profiles = schedule_profiles;
+    msg!("Schedule set with {} profiles", profiles.len());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetSchedule<'info> {
+    #[account(
+        mut,
+        constraint = phoenix_strategy.load()?.admin == admin.key() @ StrategyError::Unauthorized,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    pub admin: Signer<'info>,
+}
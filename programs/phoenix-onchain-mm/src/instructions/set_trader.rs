@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::StrategyError;
+use crate::state::PhoenixStrategyState;
+
+/// Hands quoting over to a new trader key. The new trader needs its own Phoenix seat and token
+/// accounts, and orders still resting under the old key are no longer tracked by the strategy.
+pub fn set_trader_instruction(ctx: Context<SetTrader>, new_trader: Pubkey) -> Result<()> {
+    let mut phoenix_strategy = ctx.accounts.phoenix_strategy.load_mut()?;
+    msg!(
+        "Trader changed from {} to {}",
+        phoenix_strategy.trader,
+        new_trader
+    );
+    phoenix_strategy.trader = new_trader;
+    phoenix_strategy.bid_order_sequence_number = 0;
+    phoenix_strategy.bid_price_in_ticks = 0;
+    phoenix_strategy.initial_bid_size_in_base_lots = 0;
+    phoenix_strategy.ask_order_sequence_number = 0;
+    phoenix_strategy.ask_price_in_ticks = 0;
+    phoenix_strategy.initial_ask_size_in_base_lots = 0;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetTrader<'info> {
+    #[account(
+        mut,
+        constraint = phoenix_strategy.load()?.admin == admin.key() @ StrategyError::Unauthorized,
+    )]
+    pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
+    pub admin: Signer<'info>,
+}
@@ -1,18 +1,27 @@
 use anchor_lang::{prelude::*, solana_program::program::invoke};
 use phoenix::program::{
     new_order::{CondensedOrder, MultipleOrderPacket},
-    CancelMultipleOrdersByIdParams, CancelOrderParams, MarketHeader,
+    MarketHeader,
 };
 use phoenix::{
     quantities::WrapperU64,
-    state::{markets::FIFOOrderId, OrderPacket, Side},
+    state::{
+        markets::{FIFOOrderId, FIFORestingOrder, Market},
+        OrderPacket, Side,
+    },
 };
 
-use super::{OrderParams, PriceImprovementBehavior};
-use crate::constant::{BASE, BIG_NUMBER};
+use super::{
+    FairPriceMode, OrderParams, PriceImprovementBehavior, QuoteModel, QuotePreview, StrategyParams,
+};
+use crate::constant::ORACLE_SEED;
 use crate::errors::StrategyError;
+use crate::events::VolatilityUpdated;
 use crate::oracle::{OracleConfig, PriceFeed};
 use crate::phoenix_v1::*;
+use crate::price::{
+    self, fair_price_in_ticks, size_in_base_lots, AvellanedaStoikovParams, FixedPrice,
+};
 use crate::state::PhoenixStrategyState;
 
 pub fn update_quotes_instruction(ctx: Context<UpdateQuotes>, params: OrderParams) -> Result<()> {
@@ -27,162 +36,183 @@ pub fn update_quotes_instruction(ctx: Context<UpdateQuotes>, params: OrderParams
         base_account,
         quote_vault,
         base_vault,
+        base_mint,
+        quote_mint,
         token_program,
         ..
     } = ctx.accounts;
 
+    let strategy_key = phoenix_strategy.key();
     let mut phoenix_strategy = phoenix_strategy.load_mut()?;
 
-    // Update timestamps
+    // Update the strategy parameters, also when the update is throttled below
+    apply_strategy_params(&mut phoenix_strategy, &params.strategy_params)?;
+
+    // Skip the cancels and new orders if the last update landed too recently
     let clock = Clock::get()?;
+    let slots_since_last_update = clock.slot.saturating_sub(phoenix_strategy.last_update_slot);
+    if slots_since_last_update < phoenix_strategy.min_slots_between_updates {
+        msg!(
+            "Skipping update, {} slots since the last one (minimum is {})",
+            slots_since_last_update,
+            phoenix_strategy.min_slots_between_updates
+        );
+        return Ok(());
+    }
+
+    // Update timestamps
     phoenix_strategy.last_update_slot = clock.slot;
     phoenix_strategy.last_update_unix_timestamp = clock.unix_timestamp;
 
-    // Update the strategy parameters
-    if let Some(edge) = params.strategy_params.quote_edge_in_bps {
-        if edge > 0 {
-            phoenix_strategy.quote_edge_in_bps = edge;
-        }
-    }
-    if let Some(size) = params.strategy_params.quote_size_in_quote_atoms {
-        phoenix_strategy.quote_size_in_quote_atoms = size;
-    }
-    if let Some(post_only) = params.strategy_params.post_only {
-        phoenix_strategy.post_only = post_only;
-    }
-    if let Some(price_improvement_behavior) = params.strategy_params.price_improvement_behavior {
-        phoenix_strategy.price_improvement_behavior = price_improvement_behavior.to_u8();
+    // A tripped circuit breaker keeps the strategy off the book until the owner resets it
+    if phoenix_strategy.halted {
+        msg!("Circuit breaker is tripped, cancelling all orders");
+        phoenix_strategy.initial_bid_size_in_base_lots = 0;
+        phoenix_strategy.initial_ask_size_in_base_lots = 0;
+        return cancel_all_orders(
+            phoenix_program.to_account_info(),
+            log_authority.to_account_info(),
+            user.to_account_info(),
+            market_account.to_account_info(),
+        );
     }
 
     // Load market
     let header = load_header(market_account)?;
+    check_token_program(&header, base_mint, quote_mint, token_program)?;
     let market_data = market_account.data.borrow();
     let (_, market_bytes) = market_data.split_at(std::mem::size_of::<MarketHeader>());
-    let market = phoenix::program::load_with_dispatch(&header.market_size_params, market_bytes)
-        .map_err(|_| {
-            msg!("Failed to deserialize market");
-            StrategyError::FailedToDeserializePhoenixMarket
-        })?
-        .inner;
-
-    msg!("Using oracle to calculate the fair price");
-
-    let load_base_feed = &ctx.accounts.oracle_base_price;
-
-    // With high confidence, the maximum value of the loan is
-    // (price + conf) * loan_qty * 10 ^ (expo).
-    // Here is more explanation on confidence interval in Pyth:
-    // https://docs.pyth.network/consume-data/best-practices
-    let current_timestamp1 = Clock::get()?.unix_timestamp;
-    let base_oracle_price = load_base_feed
-        .get_price_no_older_than(current_timestamp1, 60)
-        .ok_or(StrategyError::PythOffline)?;
-    msg!(
-        "oracle price = {}, oracle expo = {}",
-        base_oracle_price.price,
-        base_oracle_price.expo
-    );
-    // calculating the price by multiplying oracle price on 10^6 and dividing it on 10^expo
-    let base_fair_price = BIG_NUMBER * base_oracle_price.price as u128
-        / (u64::pow(BASE, (-base_oracle_price.expo) as u32) as u128);
+    let market = load_market(&header, market_bytes)?;
 
-    let load_quote_feed = &ctx.accounts.oracle_quote_price;
-
-    // With high confidence, the maximum value of the loan is
-    // (price + conf) * loan_qty * 10 ^ (expo).
-    // Here is more explanation on confidence interval in Pyth:
-    // https://docs.pyth.network/consume-data/best-practices
-    let current_timestamp1 = Clock::get()?.unix_timestamp;
-    let quote_oracle_price = load_quote_feed
-        .get_price_no_older_than(current_timestamp1, 60)
-        .ok_or(StrategyError::PythOffline)?;
+    let QuotePreview {
+        oracle_price_in_ticks,
+        bid_price_in_ticks,
+        bid_size_in_base_lots,
+        ask_price_in_ticks,
+        ask_size_in_base_lots,
+        ..
+    } = compute_quotes(
+        &phoenix_strategy,
+        &header,
+        market,
+        &user.key(),
+        &ctx.accounts.oracle_base_price,
+        &ctx.accounts.oracle_quote_price,
+        &clock,
+    )?;
+    let price_improvement_behavior = phoenix_strategy.get_price_improvement_behavior()?;
+
+    // Volatility and the position value use the spot oracle price, not the fair price that
+    // leans on the EMA and the book the strategy itself quotes into
+    let spot_price_in_ticks = get_spot_price_in_ticks(
+        &phoenix_strategy,
+        oracle_price_in_ticks,
+        &get_market_params(&header)?,
+        &ctx.accounts.oracle_base_price,
+        &ctx.accounts.oracle_quote_price,
+        &clock,
+    )?;
+
+    if let Some(elapsed_seconds) =
+        phoenix_strategy.update_volatility(spot_price_in_ticks, clock.unix_timestamp)
+    {
+        emit!(VolatilityUpdated {
+            strategy: strategy_key,
+            oracle_price_in_ticks: spot_price_in_ticks,
+            elapsed_seconds,
+            return_variance_in_micro_bps_squared_per_second: phoenix_strategy
+                .return_variance_in_micro_bps_squared_per_second,
+            unix_timestamp: clock.unix_timestamp,
+        });
+    }
 
-    msg!(
-        "oracle price = {}, oracle expo = {}",
-        quote_oracle_price.price,
-        quote_oracle_price.expo
+    // Detect fills on the orders placed by the last update and feed them to the circuit breaker
+    let bid_base_lots_filled = get_filled_base_lots(
+        market,
+        Side::Bid,
+        &FIFOOrderId::new_from_untyped(
+            phoenix_strategy.bid_price_in_ticks,
+            phoenix_strategy.bid_order_sequence_number,
+        ),
+        phoenix_strategy.initial_bid_size_in_base_lots,
     );
-
-    let quote_fair_price = BIG_NUMBER * quote_oracle_price.price as u128
-        / (u64::pow(BASE, (-quote_oracle_price.expo) as u32) as u128);
-
-    msg!(
-        "Base price = {}, quote price = {}",
-        base_fair_price,
-        quote_fair_price
+    let ask_base_lots_filled = get_filled_base_lots(
+        market,
+        Side::Ask,
+        &FIFOOrderId::new_from_untyped(
+            phoenix_strategy.ask_price_in_ticks,
+            phoenix_strategy.ask_order_sequence_number,
+        ),
+        phoenix_strategy.initial_ask_size_in_base_lots,
     );
-
-    let fair_price_in_ticks = get_fair_price_in_ticks(base_fair_price, quote_fair_price, &header);
-
-    // Compute quote prices
-    let mut bid_price_in_ticks =
-        get_bid_price_in_ticks(fair_price_in_ticks, phoenix_strategy.quote_edge_in_bps);
-
-    let mut ask_price_in_ticks =
-        get_ask_price_in_ticks(fair_price_in_ticks, phoenix_strategy.quote_edge_in_bps);
-
-    // Returns the best bid and ask prices that are not placed by the trader
-    let trader_index = market.get_trader_index(&user.key()).unwrap_or(u32::MAX) as u64;
-    let (best_bid, best_ask) = get_best_bid_and_ask(market, trader_index);
-
-    msg!("Current market: {} @ {}", best_bid, best_ask);
-
-    let price_improvement_behavior =
-        PriceImprovementBehavior::from_u8(phoenix_strategy.price_improvement_behavior);
-    match price_improvement_behavior {
-        PriceImprovementBehavior::Join => {
-            // If price_improvement_behavior is set to Join, we will always join the best bid and ask
-            // if our quote prices are within the spread
-            ask_price_in_ticks = ask_price_in_ticks.max(best_ask);
-            bid_price_in_ticks = bid_price_in_ticks.min(best_bid);
-        }
-        PriceImprovementBehavior::Dime => {
-            // If price_improvement_behavior is set to Dime, we will never price improve by more than 1 tick
-            ask_price_in_ticks = ask_price_in_ticks.max(best_ask - 1);
-            bid_price_in_ticks = bid_price_in_ticks.min(best_bid + 1);
-        }
-        PriceImprovementBehavior::Ignore => {
-            // If price_improvement_behavior is set to Ignore, we will not update our quotes based off the current
-            // market prices
-        }
+    if bid_base_lots_filled > 0 || ask_base_lots_filled > 0 {
+        msg!(
+            "Filled since last update: bid {} ask {}",
+            bid_base_lots_filled,
+            ask_base_lots_filled
+        );
     }
-
-    // Compute quote amounts in base lots
-    let size_in_quote_lots =
-        phoenix_strategy.quote_size_in_quote_atoms / header.get_quote_lot_size().as_u64();
-
-    let bid_size_in_base_lots = size_in_quote_lots * market.get_base_lots_per_base_unit().as_u64()
-        / (bid_price_in_ticks * market.get_tick_size().as_u64());
-    let ask_size_in_base_lots = size_in_quote_lots * market.get_base_lots_per_base_unit().as_u64()
-        / (ask_price_in_ticks * market.get_tick_size().as_u64());
-
-    msg!(
-        "Our market: {} {} @ {} {}",
-        bid_size_in_base_lots,
-        bid_price_in_ticks,
-        ask_price_in_ticks,
-        ask_size_in_base_lots
+    let bid_quote_atoms_filled = get_quote_atoms_for_base_lots(
+        bid_base_lots_filled,
+        phoenix_strategy.bid_price_in_ticks,
+        &header,
+        market,
+    )?;
+    let ask_quote_atoms_filled = get_quote_atoms_for_base_lots(
+        ask_base_lots_filled,
+        phoenix_strategy.ask_price_in_ticks,
+        &header,
+        market,
+    )?;
+    phoenix_strategy.record_fills(
+        clock.slot,
+        bid_base_lots_filled,
+        bid_quote_atoms_filled,
+        ask_base_lots_filled,
+        ask_quote_atoms_filled,
     );
+    let base_lots_position = phoenix_strategy.base_lots_position;
+    let position_value_in_quote_atoms = get_quote_atoms_for_base_lots(
+        base_lots_position.unsigned_abs(),
+        spot_price_in_ticks,
+        &header,
+        market,
+    )? as i128
+        * base_lots_position.signum() as i128;
+    if phoenix_strategy.is_circuit_breaker_tripped(position_value_in_quote_atoms) {
+        phoenix_strategy.halted = true;
+        phoenix_strategy.initial_bid_size_in_base_lots = 0;
+        phoenix_strategy.initial_ask_size_in_base_lots = 0;
+        drop(market_data);
+        return cancel_all_orders(
+            phoenix_program.to_account_info(),
+            log_authority.to_account_info(),
+            user.to_account_info(),
+            market_account.to_account_info(),
+        );
+    }
 
     let mut update_bid = true;
     let mut update_ask = true;
-    let orders_to_cancel = [
+    let tracked_bid_order_id = FIFOOrderId::new_from_untyped(
+        phoenix_strategy.bid_price_in_ticks,
+        phoenix_strategy.bid_order_sequence_number,
+    );
+    let tracked_ask_order_id = FIFOOrderId::new_from_untyped(
+        phoenix_strategy.ask_price_in_ticks,
+        phoenix_strategy.ask_order_sequence_number,
+    );
+    let mut orders_to_cancel = [
         (
             Side::Bid,
             bid_price_in_ticks,
-            FIFOOrderId::new_from_untyped(
-                phoenix_strategy.bid_price_in_ticks,
-                phoenix_strategy.bid_order_sequence_number,
-            ),
+            tracked_bid_order_id,
             phoenix_strategy.initial_bid_size_in_base_lots,
         ),
         (
             Side::Ask,
             ask_price_in_ticks,
-            FIFOOrderId::new_from_untyped(
-                phoenix_strategy.ask_price_in_ticks,
-                phoenix_strategy.ask_order_sequence_number,
-            ),
+            tracked_ask_order_id,
             phoenix_strategy.initial_ask_size_in_base_lots,
         ),
     ]
@@ -210,25 +240,39 @@ pub fn update_quotes_instruction(ctx: Context<UpdateQuotes>, params: OrderParams
     })
     .collect::<Vec<FIFOOrderId>>();
 
+    // Also cancel resting orders of the trader that the strategy does not track, e.g. left over
+    // from a failed transaction or placed by hand
+    if phoenix_strategy.cancel_untracked_orders {
+        let untracked_order_ids = get_untracked_order_ids(
+            market,
+            &user.key(),
+            &[tracked_bid_order_id, tracked_ask_order_id],
+        );
+        if !untracked_order_ids.is_empty() {
+            msg!("Cancelling {} untracked orders", untracked_order_ids.len());
+        }
+        orders_to_cancel.extend(untracked_order_ids);
+    }
+
     // Drop reference prior to invoking
     drop(market_data);
 
+    // Orders that are gone or about to be cancelled are no longer tracked, so their fills
+    // are not counted twice
+    if update_bid {
+        phoenix_strategy.initial_bid_size_in_base_lots = 0;
+    }
+    if update_ask {
+        phoenix_strategy.initial_ask_size_in_base_lots = 0;
+    }
+
     // Cancel the old orders
     if !orders_to_cancel.is_empty() {
         invoke(
             &phoenix::program::create_cancel_multiple_orders_by_id_with_free_funds_instruction(
                 &market_account.key(),
                 &user.key(),
-                &CancelMultipleOrdersByIdParams {
-                    orders: orders_to_cancel
-                        .iter()
-                        .map(|o_id| CancelOrderParams {
-                            order_sequence_number: o_id.order_sequence_number,
-                            price_in_ticks: o_id.price_in_ticks.as_u64(),
-                            side: Side::from_order_sequence_number(o_id.order_sequence_number),
-                        })
-                        .collect::<Vec<_>>(),
-                },
+                &get_cancel_params(&orders_to_cancel),
             ),
             &[
                 phoenix_program.to_account_info(),
@@ -244,6 +288,8 @@ pub fn update_quotes_instruction(ctx: Context<UpdateQuotes>, params: OrderParams
     update_ask &= ask_price_in_ticks < u64::MAX && ask_size_in_base_lots > 0;
 
     let client_order_id = u128::from_le_bytes(user.key().to_bytes()[..16].try_into().unwrap());
+    let reject_post_only = phoenix_strategy.reject_post_only;
+    let self_trade_behavior = phoenix_strategy.get_self_trade_behavior()?.to_phoenix();
     if !update_ask && !update_bid && orders_to_cancel.is_empty() {
         msg!("No orders to update");
         return Ok(());
@@ -271,7 +317,7 @@ pub fn update_quotes_instruction(ctx: Context<UpdateQuotes>, params: OrderParams
                 vec![]
             },
             Some(client_order_id),
-            false,
+            reject_post_only,
         );
         invoke(
             &phoenix::program::create_new_multiple_order_instruction_with_custom_token_accounts(
@@ -307,11 +353,14 @@ pub fn update_quotes_instruction(ctx: Context<UpdateQuotes>, params: OrderParams
                     &quote_account.key(),
                     &header.base_params.mint_key,
                     &header.quote_params.mint_key,
-                    &OrderPacket::new_limit_order_default_with_client_order_id(
+                    &OrderPacket::new_limit_order(
                         Side::Bid,
                         bid_price_in_ticks,
                         bid_size_in_base_lots,
+                        self_trade_behavior,
+                        None,
                         client_order_id,
+                        false,
                     ),
                 ),
                 &[
@@ -338,11 +387,14 @@ pub fn update_quotes_instruction(ctx: Context<UpdateQuotes>, params: OrderParams
                     &quote_account.key(),
                     &header.base_params.mint_key,
                     &header.quote_params.mint_key,
-                    &OrderPacket::new_limit_order_default_with_client_order_id(
+                    &OrderPacket::new_limit_order(
                         Side::Ask,
                         ask_price_in_ticks,
                         ask_size_in_base_lots,
+                        self_trade_behavior,
+                        None,
                         client_order_id,
+                        false,
                     ),
                 ),
                 &[
@@ -364,12 +416,22 @@ pub fn update_quotes_instruction(ctx: Context<UpdateQuotes>, params: OrderParams
 
     let market_data = market_account.data.borrow();
     let (_, market_bytes) = market_data.split_at(std::mem::size_of::<MarketHeader>());
-    let market = phoenix::program::load_with_dispatch(&header.market_size_params, market_bytes)
-        .map_err(|_| {
-            msg!("Failed to deserialize market");
-            StrategyError::FailedToDeserializePhoenixMarket
-        })?
-        .inner;
+    let market = load_market(&header, market_bytes)?;
+
+    // A new limit order can cancel or reduce a kept order of the other side through the self-trade
+    // behavior. That is not a fill, so track what is left of the kept orders.
+    if phoenix_strategy.initial_bid_size_in_base_lots > 0 {
+        phoenix_strategy.initial_bid_size_in_base_lots = market
+            .get_book(Side::Bid)
+            .get(&tracked_bid_order_id)
+            .map_or(0, |order| order.num_base_lots.as_u64());
+    }
+    if phoenix_strategy.initial_ask_size_in_base_lots > 0 {
+        phoenix_strategy.initial_ask_size_in_base_lots = market
+            .get_book(Side::Ask)
+            .get(&tracked_ask_order_id)
+            .map_or(0, |order| order.num_base_lots.as_u64());
+    }
 
     for order_id in order_ids.iter() {
         let side = Side::from_order_sequence_number(order_id.order_sequence_number);
@@ -410,16 +472,375 @@ pub fn update_quotes_instruction(ctx: Context<UpdateQuotes>, params: OrderParams
     Ok(())
 }
 
+fn cancel_all_orders<'info>(
+    phoenix_program: AccountInfo<'info>,
+    log_authority: AccountInfo<'info>,
+    trader: AccountInfo<'info>,
+    market: AccountInfo<'info>,
+) -> Result<()> {
+    invoke(
+        &phoenix::program::create_cancel_all_order_with_free_funds_instruction(
+            market.key, trader.key,
+        ),
+        &[phoenix_program, log_authority, trader, market],
+    )?;
+    Ok(())
+}
+
+/// Applies the optional strategy parameters passed with an instruction on top of the stored ones.
+/// Fails on a quote edge of 10000 bps or more.
+pub(crate) fn apply_strategy_params(
+    phoenix_strategy: &mut PhoenixStrategyState,
+    params: &StrategyParams,
+) -> Result<()> {
+    if let Some(edge) = params.quote_edge_in_bps {
+        if edge > 0 {
+            // An edge of 100% or more would quote a bid at or below zero
+            require!(edge < 10_000, StrategyError::InvalidStrategyParams);
+            phoenix_strategy.quote_edge_in_bps = edge;
+        }
+    }
+    if let Some(size) = params.quote_size_in_quote_atoms {
+        phoenix_strategy.quote_size_in_quote_atoms = size;
+    }
+    if let Some(multiplier) = params.taker_fee_edge_multiplier_in_bps {
+        phoenix_strategy.taker_fee_edge_multiplier_in_bps = multiplier;
+    }
+    if let Some(hedge_cost) = params.hedge_cost_in_bps {
+        phoenix_strategy.hedge_cost_in_bps = hedge_cost;
+    }
+    if let Some(post_only) = params.post_only {
+        phoenix_strategy.post_only = post_only;
+    }
+    if let Some(cancel_untracked_orders) = params.cancel_untracked_orders {
+        phoenix_strategy.cancel_untracked_orders = cancel_untracked_orders;
+    }
+    if let Some(reject_post_only) = params.reject_post_only {
+        phoenix_strategy.reject_post_only = reject_post_only;
+    }
+    if let Some(self_trade_behavior) = params.self_trade_behavior {
+        phoenix_strategy.self_trade_behavior = self_trade_behavior.to_u8();
+    }
+    if let Some(price_improvement_behavior) = params.price_improvement_behavior {
+        phoenix_strategy.price_improvement_behavior = price_improvement_behavior.to_u8();
+        if let PriceImprovementBehavior::PennyJumpCapped {
+            max_ticks,
+            min_edge_bps,
+        } = price_improvement_behavior
+        {
+            phoenix_strategy.penny_jump_max_ticks = max_ticks;
+            phoenix_strategy.penny_jump_min_edge_in_bps = min_edge_bps.min(9_999);
+        }
+    }
+    if let Some(min_slots_between_updates) = params.min_slots_between_updates {
+        phoenix_strategy.min_slots_between_updates = min_slots_between_updates;
+    }
+    if let Some(fair_price_mode) = params.fair_price_mode {
+        phoenix_strategy.fair_price_mode = fair_price_mode.to_u8();
+        if let FairPriceMode::Blend { ema_weight_in_bps } = fair_price_mode {
+            phoenix_strategy.ema_weight_in_bps = ema_weight_in_bps.min(10_000);
+        }
+    }
+    if let Some(quote_model) = params.quote_model {
+        // Keep the current model if the new one would divide by zero
+        if quote_model.is_valid() {
+            phoenix_strategy.quote_model = quote_model.to_u8();
+            if let QuoteModel::AvellanedaStoikov {
+                risk_aversion_ppm,
+                order_arrival_intensity_ppm,
+                horizon_in_seconds,
+            } = quote_model
+            {
+                phoenix_strategy.risk_aversion_ppm = risk_aversion_ppm;
+                phoenix_strategy.order_arrival_intensity_ppm = order_arrival_intensity_ppm;
+                phoenix_strategy.horizon_in_seconds = horizon_in_seconds;
+            }
+        } else {
+            msg!("Ignoring invalid quote model {:?}", quote_model);
+        }
+    }
+    if let Some(half_life) = params.volatility_half_life_in_seconds {
+        phoenix_strategy.volatility_half_life_in_seconds = half_life;
+    }
+    if let Some(microprice_levels) = params.microprice_levels {
+        // No levels would leave the microprice undefined
+        if microprice_levels > 0 {
+            phoenix_strategy.microprice_levels = microprice_levels;
+        }
+    }
+    if let Some(microprice_weight) = params.microprice_weight_in_bps {
+        phoenix_strategy.microprice_weight_in_bps = microprice_weight.min(10_000);
+    }
+    Ok(())
+}
+
+/// Returns the price of the base token in quote tokens, in ticks, from the Pyth prices selected
+/// by `mode`.
+pub(crate) fn get_oracle_price_in_ticks(
+    mode: FairPriceMode,
+    market_params: &price::MarketParams,
+    load_base_feed: &PriceFeed,
+    load_quote_feed: &PriceFeed,
+    clock: &Clock,
+) -> Result<u64> {
+    // With high confidence, the maximum value of the loan is
+    // (price + conf) * loan_qty * 10 ^ (expo).
+    // Here is more explanation on confidence interval in Pyth:
+    // https://docs.pyth.network/consume-data/best-practices
+    let base_oracle_price = load_base_feed
+        .get_fair_price_no_older_than(mode, clock.unix_timestamp, 60)
+        .ok_or(StrategyError::PythOffline)?;
+    msg!(
+        "oracle price = {}, oracle expo = {}",
+        base_oracle_price.price,
+        base_oracle_price.expo
+    );
+    let base_fair_price = FixedPrice::from_pyth(base_oracle_price.price, base_oracle_price.expo)?;
+
+    let quote_oracle_price = load_quote_feed
+        .get_fair_price_no_older_than(mode, clock.unix_timestamp, 60)
+        .ok_or(StrategyError::PythOffline)?;
+
+    msg!(
+        "oracle price = {}, oracle expo = {}",
+        quote_oracle_price.price,
+        quote_oracle_price.expo
+    );
+
+    let quote_fair_price =
+        FixedPrice::from_pyth(quote_oracle_price.price, quote_oracle_price.expo)?;
+
+    msg!(
+        "Base price = {}, quote price = {}",
+        base_fair_price,
+        quote_fair_price
+    );
+
+    Ok(fair_price_in_ticks(
+        base_fair_price,
+        quote_fair_price,
+        market_params,
+    )?)
+}
+
+/// Returns the spot oracle price, reusing `oracle_price_in_ticks` when the fair price mode is
+/// already spot.
+pub(crate) fn get_spot_price_in_ticks(
+    phoenix_strategy: &PhoenixStrategyState,
+    oracle_price_in_ticks: u64,
+    market_params: &price::MarketParams,
+    load_base_feed: &PriceFeed,
+    load_quote_feed: &PriceFeed,
+    clock: &Clock,
+) -> Result<u64> {
+    if phoenix_strategy.fair_price_mode == FairPriceMode::Spot.to_u8() {
+        return Ok(oracle_price_in_ticks);
+    }
+    get_oracle_price_in_ticks(
+        FairPriceMode::Spot,
+        market_params,
+        load_base_feed,
+        load_quote_feed,
+        clock,
+    )
+}
+
+/// Runs the pricing pipeline (oracle read, fair price, price improvement and sizing)
+/// without touching the book.
+pub(crate) fn compute_quotes(
+    phoenix_strategy: &PhoenixStrategyState,
+    header: &MarketHeader,
+    market: &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+    trader: &Pubkey,
+    load_base_feed: &PriceFeed,
+    load_quote_feed: &PriceFeed,
+    clock: &Clock,
+) -> Result<QuotePreview> {
+    msg!("Using oracle to calculate the fair price");
+
+    let fair_price_mode = phoenix_strategy.get_fair_price_mode()?;
+    let market_params = get_market_params(header)?;
+    let oracle_price_in_ticks = get_oracle_price_in_ticks(
+        fair_price_mode,
+        &market_params,
+        load_base_feed,
+        load_quote_feed,
+        clock,
+    )?;
+
+    let trader_index = market.get_trader_index(trader).unwrap_or(u32::MAX) as u64;
+
+    // Lean the oracle price towards the book microprice, the book often moves before the oracle
+    let microprice_in_ticks = if phoenix_strategy.microprice_weight_in_bps > 0 {
+        // Strategies initialized before levels defaulted to 1 may still store 0
+        get_microprice_in_ticks(
+            market,
+            trader_index,
+            phoenix_strategy.microprice_levels.max(1),
+        )
+    } else {
+        None
+    };
+    let fair_price_in_ticks = match microprice_in_ticks {
+        Some(microprice_in_ticks) => {
+            let weight_in_bps = phoenix_strategy.microprice_weight_in_bps.min(10_000) as u128;
+            msg!(
+                "Oracle price = {} ticks, microprice = {} ticks",
+                oracle_price_in_ticks,
+                microprice_in_ticks
+            );
+            ((oracle_price_in_ticks as u128 * (10_000 - weight_in_bps)
+                + microprice_in_ticks as u128 * weight_in_bps)
+                / 10_000) as u64
+        }
+        None => oracle_price_in_ticks,
+    };
+
+    // The edge never goes below the configured multiple of the taker fee plus the hedge cost
+    let min_edge_in_bps = get_fee_aware_min_edge_in_bps(
+        market.get_taker_fee_bps(),
+        phoenix_strategy.taker_fee_edge_multiplier_in_bps,
+        phoenix_strategy.hedge_cost_in_bps,
+    );
+    let (quote_edge_in_bps, quote_size_in_quote_atoms) =
+        phoenix_strategy.get_scheduled_quote_params(clock.unix_timestamp);
+    let edge_in_bps = quote_edge_in_bps.max(min_edge_in_bps);
+    if edge_in_bps > quote_edge_in_bps {
+        msg!(
+            "Edge raised from {} to {} bps to cover fees and hedging",
+            quote_edge_in_bps,
+            edge_in_bps
+        );
+    }
+
+    // Compute quote prices
+    let quote_model = phoenix_strategy.get_quote_model()?;
+    let (mut bid_price_in_ticks, mut ask_price_in_ticks) = match quote_model {
+        QuoteModel::FixedEdge => (
+            price::bid_price_in_ticks(fair_price_in_ticks, edge_in_bps)?,
+            price::ask_price_in_ticks(fair_price_in_ticks, edge_in_bps)?,
+        ),
+        QuoteModel::AvellanedaStoikov {
+            risk_aversion_ppm,
+            order_arrival_intensity_ppm,
+            horizon_in_seconds,
+        } => {
+            // Inventory is measured in multiples of the quote size at the fair price
+            let inventory_unit_in_base_lots = size_in_base_lots(
+                quote_size_in_quote_atoms,
+                fair_price_in_ticks,
+                &market_params,
+            )?;
+            price::avellaneda_stoikov_prices_in_ticks(
+                fair_price_in_ticks,
+                &AvellanedaStoikovParams {
+                    risk_aversion_ppm,
+                    order_arrival_intensity_ppm,
+                    horizon_in_seconds,
+                    variance_in_micro_bps_squared_per_second: phoenix_strategy
+                        .return_variance_in_micro_bps_squared_per_second,
+                },
+                phoenix_strategy.base_lots_position,
+                inventory_unit_in_base_lots,
+                edge_in_bps,
+            )?
+        }
+    };
+
+    // Returns the best bid and ask prices that are not placed by the trader
+    let (best_bid, best_ask) = get_best_bid_and_ask(market, trader_index);
+
+    msg!("Current market: {:?} @ {:?}", best_bid, best_ask);
+
+    // Sides of the book without competing orders keep the oracle-based price
+    match phoenix_strategy.get_price_improvement_behavior()? {
+        PriceImprovementBehavior::Join => {
+            // If price_improvement_behavior is set to Join, we will always join the best bid and ask
+            // if our quote prices are within the spread
+            if let Some(best_ask) = best_ask {
+                ask_price_in_ticks = ask_price_in_ticks.max(best_ask);
+            }
+            if let Some(best_bid) = best_bid {
+                bid_price_in_ticks = bid_price_in_ticks.min(best_bid);
+            }
+        }
+        PriceImprovementBehavior::Dime => {
+            // If price_improvement_behavior is set to Dime, we will never price improve by more than 1 tick
+            if let Some(best_ask) = best_ask {
+                ask_price_in_ticks = ask_price_in_ticks.max(best_ask.saturating_sub(1));
+            }
+            if let Some(best_bid) = best_bid {
+                bid_price_in_ticks = bid_price_in_ticks.min(best_bid.saturating_add(1));
+            }
+        }
+        PriceImprovementBehavior::Ignore => {
+            // If price_improvement_behavior is set to Ignore, we will not update our quotes based off the current
+            // market prices
+        }
+        PriceImprovementBehavior::PennyJumpCapped {
+            max_ticks,
+            min_edge_bps,
+        } => {
+            // Step ahead of the best competing order, but never inside the minimum edge around fair value
+            let max_bid_in_ticks = price::bid_price_in_ticks(fair_price_in_ticks, min_edge_bps)?;
+            let min_ask_in_ticks = price::ask_price_in_ticks(fair_price_in_ticks, min_edge_bps)?;
+            if let Some(best_bid) = best_bid {
+                bid_price_in_ticks = best_bid.saturating_add(max_ticks);
+            }
+            if let Some(best_ask) = best_ask {
+                ask_price_in_ticks = best_ask.saturating_sub(max_ticks);
+            }
+            bid_price_in_ticks = bid_price_in_ticks.min(max_bid_in_ticks);
+            ask_price_in_ticks = ask_price_in_ticks.max(min_ask_in_ticks);
+        }
+    }
+
+    // Compute quote amounts in base lots
+    let bid_size_in_base_lots = size_in_base_lots(
+        quote_size_in_quote_atoms,
+        bid_price_in_ticks,
+        &market_params,
+    )?;
+    let ask_size_in_base_lots = size_in_base_lots(
+        quote_size_in_quote_atoms,
+        ask_price_in_ticks,
+        &market_params,
+    )?;
+
+    msg!(
+        "Our market: {} {} @ {} {}",
+        bid_size_in_base_lots,
+        bid_price_in_ticks,
+        ask_price_in_ticks,
+        ask_size_in_base_lots
+    );
+
+    Ok(QuotePreview {
+        fair_price_in_ticks,
+        oracle_price_in_ticks,
+        microprice_in_ticks: microprice_in_ticks.unwrap_or(0),
+        edge_in_bps,
+        return_variance_in_micro_bps_squared_per_second: phoenix_strategy
+            .return_variance_in_micro_bps_squared_per_second,
+        best_bid_in_ticks: best_bid.unwrap_or(0),
+        best_ask_in_ticks: best_ask.unwrap_or(0),
+        bid_price_in_ticks,
+        bid_size_in_base_lots,
+        ask_price_in_ticks,
+        ask_size_in_base_lots,
+    })
+}
+
 #[derive(Accounts)]
 pub struct UpdateQuotes<'info> {
     #[account(
         mut,
-        seeds=[b"phoenix".as_ref(), user.key.as_ref(), market.key.as_ref()],
-        bump,
+        constraint = phoenix_strategy.load()?.trader == user.key() @ StrategyError::Unauthorized,
+        constraint = phoenix_strategy.load()?.market == market.key() @ StrategyError::InvalidArgument,
     )]
     pub phoenix_strategy: AccountLoader<'info, PhoenixStrategyState>,
     #[account(
-            seeds = [b"oracle", user.key.as_ref(), market.key.as_ref()],
+            seeds = [ORACLE_SEED, phoenix_strategy.key().as_ref()],
             bump
     )]
     pub oracle_account: Account<'info, OracleConfig>,
@@ -452,6 +873,91 @@ pub struct UpdateQuotes<'info> {
     /// CHECK: Checked in CPI
     #[account(mut)]
     pub base_vault: UncheckedAccount<'info>,
-    /// CHECK: Checked in CPI
+    /// CHECK: Checked against the market in instruction
+    pub base_mint: UncheckedAccount<'info>,
+    /// CHECK: Checked against the market in instruction
+    pub quote_mint: UncheckedAccount<'info>,
+    /// CHECK: Checked against the mint owners in instruction and in CPI
     pub token_program: UncheckedAccount<'info>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::__private::bytemuck::Zeroable;
+    use price::update_variance_ewma;
+
+    const MARKET: price::MarketParams = price::MarketParams {
+        quote_decimals: 6,
+        raw_base_units_per_base_unit: 1,
+        tick_size_in_quote_atoms_per_base_unit: 1_000,
+        base_lots_per_base_unit: 1_000,
+    };
+
+    fn feed(price: i64, ema_price: i64, publish_time: i64) -> PriceFeed {
+        let at = |price| pyth_sdk::Price {
+            price,
+            conf: 0,
+            expo: -2,
+            publish_time,
+        };
+        pyth_sdk::PriceFeed::new(pyth_sdk::Identifier::new([0; 32]), at(price), at(ema_price))
+            .into()
+    }
+
+    #[test]
+    fn test_volatility_follows_spot_price_in_ema_mode() {
+        let mut strategy = PhoenixStrategyState::zeroed();
+        strategy.fair_price_mode = FairPriceMode::Ema.to_u8();
+        strategy.volatility_half_life_in_seconds = 60;
+
+        let mut expected_variance = 0;
+        let mut last_spot_price_in_ticks = 0;
+        for (unix_timestamp, spot_price) in [(0, 10_000), (10, 10_100), (20, 9_900)] {
+            let clock = Clock {
+                unix_timestamp,
+                ..Default::default()
+            };
+            let quote_feed = feed(100, 100, unix_timestamp);
+            // The EMA never moves, so a variance measured on the fair price would stay at zero
+            let base_feed = feed(spot_price, 10_000, unix_timestamp);
+            let oracle_price_in_ticks = get_oracle_price_in_ticks(
+                FairPriceMode::Ema,
+                &MARKET,
+                &base_feed,
+                &quote_feed,
+                &clock,
+            )
+            .unwrap();
+            assert_eq!(oracle_price_in_ticks, 100_000);
+
+            let spot_price_in_ticks = get_spot_price_in_ticks(
+                &strategy,
+                oracle_price_in_ticks,
+                &MARKET,
+                &base_feed,
+                &quote_feed,
+                &clock,
+            )
+            .unwrap();
+            assert_eq!(spot_price_in_ticks, spot_price as u64 * 10);
+
+            strategy.update_volatility(spot_price_in_ticks, unix_timestamp);
+            expected_variance = update_variance_ewma(
+                expected_variance,
+                last_spot_price_in_ticks,
+                spot_price_in_ticks,
+                if last_spot_price_in_ticks == 0 { 0 } else { 10 },
+                60,
+            );
+            last_spot_price_in_ticks = spot_price_in_ticks;
+        }
+
+        assert!(expected_variance > 0);
+        assert_eq!(
+            strategy.return_variance_in_micro_bps_squared_per_second,
+            expected_variance
+        );
+        assert_eq!(strategy.last_oracle_price_in_ticks, 99_000);
+    }
+}
@@ -2,11 +2,15 @@
 
 use anchor_lang::prelude::*;
 
+#[cfg(feature = "client")]
+pub mod client;
 pub mod constant;
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod oracle;
 pub mod phoenix_v1;
+pub mod price;
 pub mod state;
 
 pub use instructions::*;
@@ -21,7 +25,64 @@ pub mod phoenix_onchain_mm {
         initialize_instruction(ctx, params)
     }
 
+    pub fn migrate(ctx: Context<Migrate>) -> Result<()> {
+        migrate_instruction(ctx)
+    }
+
     pub fn update_quotes(ctx: Context<UpdateQuotes>, params: OrderParams) -> Result<()> {
         update_quotes_instruction(ctx, params)
     }
+
+    pub fn preview_quotes(
+        ctx: Context<PreviewQuotes>,
+        params: OrderParams,
+    ) -> Result<QuotePreview> {
+        preview_quotes_instruction(ctx, params)
+    }
+
+    pub fn reset_circuit_breaker(ctx: Context<ResetCircuitBreaker>) -> Result<()> {
+        reset_circuit_breaker_instruction(ctx)
+    }
+
+    pub fn set_circuit_breaker(
+        ctx: Context<SetCircuitBreaker>,
+        params: CircuitBreakerParams,
+    ) -> Result<()> {
+        set_circuit_breaker_instruction(ctx, params)
+    }
+
+    pub fn cancel_all(ctx: Context<CancelAll>) -> Result<()> {
+        cancel_all_instruction(ctx)
+    }
+
+    pub fn reconcile(ctx: Context<Reconcile>) -> Result<()> {
+        reconcile_instruction(ctx)
+    }
+
+    pub fn set_schedule(
+        ctx: Context<SetSchedule>,
+        profiles: Vec<ScheduleProfileParams>,
+    ) -> Result<()> {
+        set_schedule_instruction(ctx, profiles)
+    }
+
+    pub fn halt(ctx: Context<Halt>) -> Result<()> {
+        halt_instruction(ctx)
+    }
+
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        propose_admin_instruction(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        accept_admin_instruction(ctx)
+    }
+
+    pub fn set_trader(ctx: Context<SetTrader>, new_trader: Pubkey) -> Result<()> {
+        set_trader_instruction(ctx, new_trader)
+    }
+
+    pub fn set_guardian(ctx: Context<SetGuardian>, new_guardian: Pubkey) -> Result<()> {
+        set_guardian_instruction(ctx, new_guardian)
+    }
 }
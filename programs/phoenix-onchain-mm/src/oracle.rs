@@ -4,6 +4,7 @@ use std::ops::Deref;
 use std::str::FromStr;
 
 use crate::errors::StrategyError;
+use crate::instructions::FairPriceMode;
 
 #[account]
 #[derive(Debug, Copy)]
@@ -29,6 +30,42 @@ impl anchor_lang::Owner for PriceFeed {
     }
 }
 
+impl From<pyth_sdk::PriceFeed> for PriceFeed {
+    fn from(feed: pyth_sdk::PriceFeed) -> Self {
+        PriceFeed(feed)
+    }
+}
+
+impl PriceFeed {
+    /// Returns the price selected by `mode`, or `None` if any price it needs is older than `age` seconds.
+    pub fn get_fair_price_no_older_than(
+        &self,
+        mode: FairPriceMode,
+        current_time: i64,
+        age: u64,
+    ) -> Option<pyth_sdk::Price> {
+        match mode {
+            FairPriceMode::Spot => self.get_price_no_older_than(current_time, age),
+            FairPriceMode::Ema => self.get_ema_price_no_older_than(current_time, age),
+            FairPriceMode::Blend { ema_weight_in_bps } => {
+                let spot = self.get_price_no_older_than(current_time, age)?;
+                let ema = self.get_ema_price_no_older_than(current_time, age)?;
+                // Spot and EMA prices of a feed share the same exponent
+                let ema_weight_in_bps = ema_weight_in_bps.min(10_000) as i128;
+                let price = (spot.price as i128 * (10_000 - ema_weight_in_bps)
+                    + ema.price as i128 * ema_weight_in_bps)
+                    / 10_000;
+                Some(pyth_sdk::Price {
+                    price: price as i64,
+                    conf: spot.conf.max(ema.conf),
+                    expo: spot.expo,
+                    publish_time: spot.publish_time.min(ema.publish_time),
+                })
+            }
+        }
+    }
+}
+
 impl anchor_lang::AccountDeserialize for PriceFeed {
     fn try_deserialize_unchecked(data: &mut &[u8]) -> Result<Self> {
         let account = load_price_account(data).map_err(|_x| error!(StrategyError::PythError))?;
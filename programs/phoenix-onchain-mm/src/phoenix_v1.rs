@@ -3,7 +3,7 @@ use anchor_lang::{
     prelude::*,
     solana_program::program::get_return_data,
 };
-use phoenix::program::MarketHeader;
+use phoenix::program::{CancelMultipleOrdersByIdParams, CancelOrderParams, MarketHeader};
 use phoenix::{
     quantities::WrapperU64,
     state::{
@@ -12,7 +12,9 @@ use phoenix::{
     },
 };
 
+use crate::constant::{self, MAX_UNTRACKED_ORDERS_TO_CANCEL};
 use crate::errors::StrategyError;
+use crate::price::{MarketParams, PriceError};
 
 pub const PHOENIX_MARKET_DISCRIMINANT: u64 = 8167313896524341111;
 
@@ -25,6 +27,34 @@ impl anchor_lang::Id for PhoenixV1 {
     }
 }
 
+/// Checks the mint accounts against the market and that `token_program` is the classic or
+/// Token-2022 program owning both of them.
+pub fn check_token_program(
+    header: &MarketHeader,
+    base_mint: &AccountInfo,
+    quote_mint: &AccountInfo,
+    token_program: &AccountInfo,
+) -> Result<()> {
+    require_keys_eq!(
+        base_mint.key(),
+        header.base_params.mint_key,
+        StrategyError::InvalidArgument
+    );
+    require_keys_eq!(
+        quote_mint.key(),
+        header.quote_params.mint_key,
+        StrategyError::InvalidArgument
+    );
+    require!(
+        (token_program.key() == constant::token_program::ID
+            || token_program.key() == constant::token_2022_program::ID)
+            && *base_mint.owner == token_program.key()
+            && *quote_mint.owner == token_program.key(),
+        StrategyError::InvalidTokenProgram
+    );
+    Ok(())
+}
+
 #[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy)]
 struct DeserializedFIFOOrderId {
     pub price_in_ticks: u64,
@@ -70,54 +100,220 @@ pub fn load_header(info: &AccountInfo) -> Result<MarketHeader> {
     Ok(*header)
 }
 
+pub fn load_market<'a>(
+    header: &'a MarketHeader,
+    market_bytes: &'a [u8],
+) -> Result<&'a dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>> {
+    Ok(
+        phoenix::program::load_with_dispatch(&header.market_size_params, market_bytes)
+            .map_err(|_| {
+                msg!("Failed to deserialize market");
+                StrategyError::FailedToDeserializePhoenixMarket
+            })?
+            .inner,
+    )
+}
+
+/// Returns the best bid and ask prices of orders not placed by `trader_index`, `None` for an empty side.
 pub fn get_best_bid_and_ask(
     market: &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
     trader_index: u64,
-) -> (u64, u64) {
+) -> (Option<u64>, Option<u64>) {
     let best_bid = market
         .get_book(Side::Bid)
         .iter()
         .find(|(_, o)| o.trader_index != trader_index)
-        .map(|(o, _)| o.price_in_ticks.as_u64())
-        .unwrap_or_else(|| 1);
+        .map(|(o, _)| o.price_in_ticks.as_u64());
     let best_ask = market
         .get_book(Side::Ask)
         .iter()
         .find(|(_, o)| o.trader_index != trader_index)
-        .map(|(o, _)| o.price_in_ticks.as_u64())
-        .unwrap_or_else(|| u64::MAX);
+        .map(|(o, _)| o.price_in_ticks.as_u64());
     (best_bid, best_ask)
 }
 
-pub fn get_bid_price_in_ticks(fair_price_in_ticks: u64, edge_in_bps: u64) -> u64 {
-    let edge_in_ticks = edge_in_bps * fair_price_in_ticks / 10_000;
-    fair_price_in_ticks - edge_in_ticks
+/// Returns the ids of the resting orders of `trader` that are not in `tracked_order_ids`,
+/// at most `MAX_UNTRACKED_ORDERS_TO_CANCEL` of them so the cancel fits in one transaction.
+pub fn get_untracked_order_ids(
+    market: &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+    trader: &Pubkey,
+    tracked_order_ids: &[FIFOOrderId],
+) -> Vec<FIFOOrderId> {
+    let trader_index = match market.get_trader_index(trader) {
+        Some(trader_index) => trader_index as u64,
+        None => return vec![],
+    };
+    [Side::Bid, Side::Ask]
+        .iter()
+        .flat_map(|side| {
+            market
+                .get_book(*side)
+                .iter()
+                .filter(|(order_id, order)| {
+                    order.trader_index == trader_index && !tracked_order_ids.contains(order_id)
+                })
+                .map(|(order_id, _)| *order_id)
+        })
+        .take(MAX_UNTRACKED_ORDERS_TO_CANCEL)
+        .collect()
+}
+
+pub fn get_cancel_params(order_ids: &[FIFOOrderId]) -> CancelMultipleOrdersByIdParams {
+    CancelMultipleOrdersByIdParams {
+        orders: order_ids
+            .iter()
+            .map(|o_id| CancelOrderParams {
+                order_sequence_number: o_id.order_sequence_number,
+                price_in_ticks: o_id.price_in_ticks.as_u64(),
+                side: Side::from_order_sequence_number(o_id.order_sequence_number),
+            })
+            .collect::<Vec<_>>(),
+    }
 }
 
-pub fn get_ask_price_in_ticks(fair_price_in_ticks: u64, edge_in_bps: u64) -> u64 {
-    let edge_in_ticks = edge_in_bps * fair_price_in_ticks / 10_000;
-    fair_price_in_ticks + edge_in_ticks
+/// Returns the best price and the total size in base lots of the first `levels` price levels of `side`,
+/// ignoring the orders of `trader_index`. Returns `None` if no other trader is on that side.
+pub fn get_depth_in_base_lots(
+    market: &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+    side: Side,
+    trader_index: u64,
+    levels: u64,
+) -> Option<(u64, u64)> {
+    let mut best_price_in_ticks = None;
+    let mut level_price_in_ticks = 0;
+    let mut levels_seen = 0;
+    let mut depth_in_base_lots = 0_u64;
+    for (order_id, order) in market.get_book(side).iter() {
+        if order.trader_index == trader_index {
+            continue;
+        }
+        let price_in_ticks = order_id.price_in_ticks.as_u64();
+        if levels_seen == 0 || price_in_ticks != level_price_in_ticks {
+            if levels_seen == levels {
+                break;
+            }
+            levels_seen += 1;
+            level_price_in_ticks = price_in_ticks;
+            best_price_in_ticks.get_or_insert(price_in_ticks);
+        }
+        depth_in_base_lots = depth_in_base_lots.saturating_add(order.num_base_lots.as_u64());
+    }
+    best_price_in_ticks.map(|price| (price, depth_in_base_lots))
 }
 
-#[allow(dead_code)]
-fn common_for_bid_and_ask_price_in_ticks(
-    fair_price_in_quote_atoms_per_raw_base_unit: u64,
+/// Returns the size-weighted mid price of the other traders' orders, which leans towards the side
+/// with less depth in the top `levels` price levels. Returns `None` if either side of the book is empty.
+pub fn get_microprice_in_ticks(
+    market: &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+    trader_index: u64,
+    levels: u64,
+) -> Option<u64> {
+    let (best_bid, bid_depth) = get_depth_in_base_lots(market, Side::Bid, trader_index, levels)?;
+    let (best_ask, ask_depth) = get_depth_in_base_lots(market, Side::Ask, trader_index, levels)?;
+    let total_depth = bid_depth as u128 + ask_depth as u128;
+    if total_depth == 0 {
+        return None;
+    }
+    Some(
+        ((best_ask as u128 * bid_depth as u128 + best_bid as u128 * ask_depth as u128)
+            / total_depth) as u64,
+    )
+}
+
+/// Returns how many base lots of an order placed by the strategy were filled since it was placed.
+/// An order that is no longer on the book is treated as fully filled.
+pub fn get_filled_base_lots(
+    market: &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+    side: Side,
+    order_id: &FIFOOrderId,
+    initial_size_in_base_lots: u64,
+) -> u64 {
+    match market.get_book(side).get(order_id) {
+        Some(resting_order) => {
+            initial_size_in_base_lots.saturating_sub(resting_order.num_base_lots.as_u64())
+        }
+        None => initial_size_in_base_lots,
+    }
+}
+
+/// Returns the value in quote atoms of `base_lots` at `price_in_ticks`, failing if it does not
+/// fit in a u64.
+pub fn get_quote_atoms_for_base_lots(
+    base_lots: u64,
+    price_in_ticks: u64,
     header: &MarketHeader,
-    edge_in_bps: u64,
-) -> (u64, u64) {
-    let fair_price_in_ticks = fair_price_in_quote_atoms_per_raw_base_unit
-        * header.raw_base_units_per_base_unit as u64
-        / header.get_tick_size_in_quote_atoms_per_base_unit().as_u64();
-    let edge_in_ticks = edge_in_bps * fair_price_in_ticks / 10_000;
-    (fair_price_in_ticks, edge_in_ticks)
-}
-
-pub fn get_fair_price_in_ticks(base_price: u128, quote_price: u128, header: &MarketHeader) -> u64 {
-    (base_price
-        * (u64::pow(10, header.quote_params.decimals) as u128)
-        * header.raw_base_units_per_base_unit as u128
-        / header
+    market: &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+) -> Result<u64> {
+    let quote_atoms = (base_lots as u128 * price_in_ticks as u128)
+        .checked_mul(market.get_tick_size().as_u64() as u128)
+        .and_then(|value| value.checked_mul(header.get_quote_lot_size().as_u64() as u128))
+        .ok_or(StrategyError::MathOverflow)?
+        .checked_div(market.get_base_lots_per_base_unit().as_u64() as u128)
+        .ok_or(StrategyError::DivisionByZero)?;
+    Ok(u64::try_from(quote_atoms).map_err(|_| StrategyError::MathOverflow)?)
+}
+
+/// Returns the smallest edge that still pays `taker_fee_multiplier_in_bps / 10_000` times the taker fee
+/// plus the cost of hedging the fill. Both settings at 0 disable the floor.
+pub fn get_fee_aware_min_edge_in_bps(
+    taker_fee_bps: u64,
+    taker_fee_multiplier_in_bps: u64,
+    hedge_cost_in_bps: u64,
+) -> u64 {
+    // Round the fee part up so the floor is never below the fee
+    let fee_in_bps = taker_fee_bps
+        .saturating_mul(taker_fee_multiplier_in_bps)
+        .saturating_add(9_999)
+        / 10_000;
+    fee_in_bps.saturating_add(hedge_cost_in_bps)
+}
+
+/// Reads the sizes the price math needs from the market header, failing on a header whose
+/// decimals overflow or whose base lot size is zero.
+pub fn get_market_params(header: &MarketHeader) -> std::result::Result<MarketParams, PriceError> {
+    let base_atoms_per_base_unit = 10u64
+        .checked_pow(header.base_params.decimals)
+        .and_then(|atoms| atoms.checked_mul(header.raw_base_units_per_base_unit as u64))
+        .ok_or(PriceError::Overflow)?;
+    Ok(MarketParams {
+        quote_decimals: header.quote_params.decimals,
+        raw_base_units_per_base_unit: header.raw_base_units_per_base_unit,
+        tick_size_in_quote_atoms_per_base_unit: header
             .get_tick_size_in_quote_atoms_per_base_unit()
-            .as_u128()
-        / quote_price) as u64
+            .as_u64(),
+        base_lots_per_base_unit: base_atoms_per_base_unit
+            .checked_div(header.get_base_lot_size().as_u64())
+            .ok_or(PriceError::DivisionByZero)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_market_params_rejects_invalid_headers() {
+        // A zeroed header has a base lot size of zero
+        let mut header = <MarketHeader as bytemuck::Zeroable>::zeroed();
+        header.base_params.decimals = 9;
+        header.raw_base_units_per_base_unit = 1;
+        assert_eq!(
+            get_market_params(&header).unwrap_err(),
+            PriceError::DivisionByZero
+        );
+
+        header.base_params.decimals = 20;
+        assert_eq!(
+            get_market_params(&header).unwrap_err(),
+            PriceError::Overflow
+        );
+
+        // 10^19 fits in a u64, twice that does not
+        header.base_params.decimals = 19;
+        header.raw_base_units_per_base_unit = 2;
+        assert_eq!(
+            get_market_params(&header).unwrap_err(),
+            PriceError::Overflow
+        );
+    }
 }
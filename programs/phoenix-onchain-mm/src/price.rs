@@ -0,0 +1,564 @@
+//! Fixed-point price math shared by the program and `pomm`, so off-chain previews match the
+//! quotes placed on chain.
+//!
+//! All arithmetic is checked. Rounding always favours the strategy: bid prices round down,
+//! ask prices round up and order sizes round down to whole base lots.
+
+use std::fmt;
+
+/// Number of decimal digits kept by [`FixedPrice`].
+pub const PRICE_DECIMALS: u32 = 12;
+
+const PRICE_ONE: u128 = 1_000_000_000_000;
+
+const BPS_ONE: u128 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceError {
+    Overflow,
+    DivisionByZero,
+    NegativePrice,
+    EdgeTooLarge,
+}
+
+impl fmt::Display for PriceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceError::Overflow => write!(f, "price math overflowed"),
+            PriceError::DivisionByZero => write!(f, "price math divided by zero"),
+            PriceError::NegativePrice => write!(f, "oracle price is negative"),
+            PriceError::EdgeTooLarge => write!(f, "edge must be below 10000 bps"),
+        }
+    }
+}
+
+impl std::error::Error for PriceError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// Computes `a * b / c` without intermediate rounding.
+pub fn mul_div(a: u128, b: u128, c: u128, rounding: Rounding) -> Result<u128, PriceError> {
+    if c == 0 {
+        return Err(PriceError::DivisionByZero);
+    }
+    let product = a.checked_mul(b).ok_or(PriceError::Overflow)?;
+    let quotient = product / c;
+    match rounding {
+        Rounding::Up if product % c != 0 => quotient.checked_add(1).ok_or(PriceError::Overflow),
+        _ => Ok(quotient),
+    }
+}
+
+fn pow10(exponent: u32) -> Result<u128, PriceError> {
+    10_u128.checked_pow(exponent).ok_or(PriceError::Overflow)
+}
+
+fn to_u64(value: u128) -> Result<u64, PriceError> {
+    u64::try_from(value).map_err(|_| PriceError::Overflow)
+}
+
+/// A non-negative price with [`PRICE_DECIMALS`] decimal digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FixedPrice(u128);
+
+impl FixedPrice {
+    pub fn from_raw(raw: u128) -> Self {
+        Self(raw)
+    }
+
+    pub fn raw(&self) -> u128 {
+        self.0
+    }
+
+    /// Converts a Pyth price of `price * 10^expo`. Digits beyond [`PRICE_DECIMALS`] are rounded down.
+    pub fn from_pyth(price: i64, expo: i32) -> Result<Self, PriceError> {
+        if price < 0 {
+            return Err(PriceError::NegativePrice);
+        }
+        let price = price as u128;
+        let shift = expo + PRICE_DECIMALS as i32;
+        if shift >= 0 {
+            price
+                .checked_mul(pow10(shift as u32)?)
+                .map(Self)
+                .ok_or(PriceError::Overflow)
+        } else {
+            // Anything past 10^38 is larger than any i64 price
+            Ok(Self(match pow10(shift.unsigned_abs()) {
+                Ok(divisor) => price / divisor,
+                Err(_) => 0,
+            }))
+        }
+    }
+}
+
+impl fmt::Display for FixedPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:012}", self.0 / PRICE_ONE, self.0 % PRICE_ONE)
+    }
+}
+
+/// The parts of a Phoenix market needed to convert prices to ticks and sizes to base lots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketParams {
+    pub quote_decimals: u32,
+    pub raw_base_units_per_base_unit: u32,
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+    pub base_lots_per_base_unit: u64,
+}
+
+/// Returns the fair price of one base unit in ticks, rounded down.
+/// Both prices must be quoted in the same currency, usually USD.
+pub fn fair_price_in_ticks(
+    base_price: FixedPrice,
+    quote_price: FixedPrice,
+    market: &MarketParams,
+) -> Result<u64, PriceError> {
+    let quote_atoms_per_quote_unit = pow10(market.quote_decimals)?;
+    let numerator = base_price
+        .raw()
+        .checked_mul(quote_atoms_per_quote_unit)
+        .and_then(|n| n.checked_mul(market.raw_base_units_per_base_unit as u128))
+        .ok_or(PriceError::Overflow)?;
+    let denominator = quote_price
+        .raw()
+        .checked_mul(market.tick_size_in_quote_atoms_per_base_unit as u128)
+        .ok_or(PriceError::Overflow)?;
+    to_u64(mul_div(numerator, 1, denominator, Rounding::Down)?)
+}
+
+/// Returns `fair_price_in_ticks` lowered by `edge_in_bps`, rounded down.
+pub fn bid_price_in_ticks(fair_price_in_ticks: u64, edge_in_bps: u64) -> Result<u64, PriceError> {
+    let edge_in_bps = edge_in_bps as u128;
+    if edge_in_bps >= BPS_ONE {
+        return Err(PriceError::EdgeTooLarge);
+    }
+    to_u64(mul_div(
+        fair_price_in_ticks as u128,
+        BPS_ONE - edge_in_bps,
+        BPS_ONE,
+        Rounding::Down,
+    )?)
+}
+
+/// Returns `fair_price_in_ticks` raised by `edge_in_bps`, rounded up.
+pub fn ask_price_in_ticks(fair_price_in_ticks: u64, edge_in_bps: u64) -> Result<u64, PriceError> {
+    to_u64(mul_div(
+        fair_price_in_ticks as u128,
+        BPS_ONE + edge_in_bps as u128,
+        BPS_ONE,
+        Rounding::Up,
+    )?)
+}
+
+/// Returns how many base lots `quote_size_in_quote_atoms` buys at `price_in_ticks`, rounded down.
+pub fn size_in_base_lots(
+    quote_size_in_quote_atoms: u64,
+    price_in_ticks: u64,
+    market: &MarketParams,
+) -> Result<u64, PriceError> {
+    let quote_atoms_per_base_unit = (price_in_ticks as u128)
+        .checked_mul(market.tick_size_in_quote_atoms_per_base_unit as u128)
+        .ok_or(PriceError::Overflow)?;
+    to_u64(mul_div(
+        quote_size_in_quote_atoms as u128,
+        market.base_lots_per_base_unit as u128,
+        quote_atoms_per_base_unit,
+        Rounding::Down,
+    )?)
+}
+
+/// ln(2) scaled by [`PRICE_ONE`]
+const LN_2: u128 = 693_147_180_560;
+
+/// Returns `ln(1 + x)` with `x` and the result scaled by [`PRICE_ONE`].
+fn ln_one_plus(x: u128) -> Result<u128, PriceError> {
+    let mut m = PRICE_ONE.checked_add(x).ok_or(PriceError::Overflow)?;
+    // Reduce to m in [1, 2) with ln(2^n * m) = n * ln(2) + ln(m)
+    let mut result = 0;
+    while m >= 2 * PRICE_ONE {
+        m /= 2;
+        result += LN_2;
+    }
+    // ln(m) = 2 * atanh(z) = 2 * (z + z^3 / 3 + z^5 / 5 + ...) with z = (m - 1) / (m + 1) < 1/3
+    let z = mul_div(m - PRICE_ONE, PRICE_ONE, m + PRICE_ONE, Rounding::Down)?;
+    let z_squared = z * z / PRICE_ONE;
+    let mut term = z;
+    let mut series = 0;
+    let mut n = 1;
+    while term > 0 {
+        series += term / n;
+        term = term * z_squared / PRICE_ONE;
+        n += 2;
+    }
+    Ok(result + 2 * series)
+}
+
+/// Parameters of the Avellaneda–Stoikov model. Prices are measured in bps of the fair price
+/// and inventory in multiples of the quote size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AvellanedaStoikovParams {
+    /// Risk aversion γ in millionths per bps
+    pub risk_aversion_ppm: u64,
+    /// Order arrival intensity decay k in millionths per bps from the fair price
+    pub order_arrival_intensity_ppm: u64,
+    /// Remaining horizon T - t the inventory risk is priced over
+    pub horizon_in_seconds: u64,
+    /// Variance σ² of the oracle returns in millionths of bps² per second
+    pub variance_in_micro_bps_squared_per_second: u64,
+}
+
+/// Returns the Avellaneda–Stoikov bid and ask around `fair_price_in_ticks`.
+///
+/// The reservation price moves against the inventory q by q·γ·σ²·T and the quotes sit
+/// γ·σ²·T / 2 + ln(1 + γ / k) / γ on either side of it, but never closer than
+/// `min_half_spread_in_bps`. q is `inventory_in_base_lots / inventory_unit_in_base_lots`
+/// and is ignored when the unit is 0. A bid that would fall below zero is 0.
+pub fn avellaneda_stoikov_prices_in_ticks(
+    fair_price_in_ticks: u64,
+    params: &AvellanedaStoikovParams,
+    inventory_in_base_lots: i64,
+    inventory_unit_in_base_lots: u64,
+    min_half_spread_in_bps: u64,
+) -> Result<(u64, u64), PriceError> {
+    let risk_aversion = params.risk_aversion_ppm as u128;
+    let intensity = params.order_arrival_intensity_ppm as u128;
+    if risk_aversion == 0 || intensity == 0 {
+        return Err(PriceError::DivisionByZero);
+    }
+
+    // All terms below are in bps scaled by PRICE_ONE, the ppm scalings of γ and σ² cancel it out
+    let inventory_risk = risk_aversion
+        .checked_mul(params.variance_in_micro_bps_squared_per_second as u128)
+        .and_then(|n| n.checked_mul(params.horizon_in_seconds as u128))
+        .ok_or(PriceError::Overflow)?;
+    let log_term = ln_one_plus(mul_div(
+        risk_aversion,
+        PRICE_ONE,
+        intensity,
+        Rounding::Down,
+    )?)?;
+    let intensity_term = mul_div(log_term, 1_000_000, risk_aversion, Rounding::Down)?;
+    let min_half_spread = (min_half_spread_in_bps as u128)
+        .checked_mul(PRICE_ONE)
+        .ok_or(PriceError::Overflow)?;
+    let half_spread = (inventory_risk / 2)
+        .checked_add(intensity_term)
+        .ok_or(PriceError::Overflow)?
+        .max(min_half_spread);
+
+    let reservation_offset = if inventory_unit_in_base_lots == 0 {
+        0
+    } else {
+        mul_div(
+            inventory_risk,
+            inventory_in_base_lots.unsigned_abs() as u128,
+            inventory_unit_in_base_lots as u128,
+            Rounding::Down,
+        )?
+    };
+    // Long inventory lowers both quotes, short inventory raises them
+    let (bid_edge, ask_edge) = if inventory_in_base_lots >= 0 {
+        (
+            half_spread
+                .checked_add(reservation_offset)
+                .ok_or(PriceError::Overflow)?,
+            half_spread as i128 - reservation_offset as i128,
+        )
+    } else {
+        (
+            half_spread.saturating_sub(reservation_offset),
+            half_spread
+                .checked_add(reservation_offset)
+                .ok_or(PriceError::Overflow)? as i128,
+        )
+    };
+
+    let one = BPS_ONE * PRICE_ONE;
+    let bid_price_in_ticks = to_u64(mul_div(
+        fair_price_in_ticks as u128,
+        one.saturating_sub(bid_edge),
+        one,
+        Rounding::Down,
+    )?)?;
+    let ask_factor = if ask_edge >= 0 {
+        one.checked_add(ask_edge as u128)
+            .ok_or(PriceError::Overflow)?
+    } else {
+        one.saturating_sub(ask_edge.unsigned_abs())
+    };
+    let ask_price_in_ticks = to_u64(mul_div(
+        fair_price_in_ticks as u128,
+        ask_factor,
+        one,
+        Rounding::Up,
+    )?)?;
+    Ok((bid_price_in_ticks, ask_price_in_ticks))
+}
+
+/// Returns 2^(-elapsed / half_life) scaled by [`PRICE_ONE`], 0 when `half_life` is 0.
+fn half_life_decay(elapsed: u64, half_life: u64) -> Result<u128, PriceError> {
+    if half_life == 0 {
+        return Ok(0);
+    }
+    let halvings = elapsed / half_life;
+    if halvings >= 64 {
+        return Ok(0);
+    }
+    // 2^(-f) = 1 / exp(f * ln(2)) for the fractional part f, with f * ln(2) < 0.7
+    let exponent = mul_div(
+        (elapsed % half_life) as u128,
+        LN_2,
+        half_life as u128,
+        Rounding::Down,
+    )?;
+    let mut term = PRICE_ONE;
+    let mut exp = PRICE_ONE;
+    let mut n = 1;
+    while term > 0 {
+        term = term * exponent / PRICE_ONE / n;
+        exp += term;
+        n += 1;
+    }
+    Ok(mul_div(PRICE_ONE, PRICE_ONE, exp, Rounding::Down)? >> halvings)
+}
+
+/// Folds the return between two prices into an exponentially weighted variance in millionths
+/// of bps² per second. The previous estimate loses half its weight every `half_life_in_seconds`.
+/// Returns the variance unchanged when there is no previous price or no time has passed.
+/// Never fails: a return too large to represent saturates the sample at `u64::MAX`.
+pub fn update_variance_ewma(
+    variance_in_micro_bps_squared_per_second: u64,
+    previous_price_in_ticks: u64,
+    price_in_ticks: u64,
+    elapsed_seconds: u64,
+    half_life_in_seconds: u64,
+) -> u64 {
+    if previous_price_in_ticks == 0 || elapsed_seconds == 0 {
+        return variance_in_micro_bps_squared_per_second;
+    }
+    // Only fails on overflow, which means the previous estimate has fully decayed
+    let decay = half_life_decay(elapsed_seconds, half_life_in_seconds).unwrap_or(0);
+    let change = previous_price_in_ticks.abs_diff(price_in_ticks) as u128;
+    // (change / previous * 10^4)^2 * 10^6 / elapsed, rounding the bps move first on overflow
+    let sample = (change * change)
+        .checked_mul(100_000_000_000_000)
+        .zip(
+            (previous_price_in_ticks as u128 * previous_price_in_ticks as u128)
+                .checked_mul(elapsed_seconds as u128),
+        )
+        .map(|(numerator, denominator)| numerator / denominator)
+        .unwrap_or_else(|| {
+            let move_in_scaled_bps = change * 10_000_000 / previous_price_in_ticks as u128;
+            move_in_scaled_bps.saturating_mul(move_in_scaled_bps) / elapsed_seconds as u128
+        })
+        .min(u64::MAX as u128);
+    // Both terms are below 2^64 * PRICE_ONE so the sum cannot overflow
+    let weighted_sum =
+        sample * (PRICE_ONE - decay) + variance_in_micro_bps_squared_per_second as u128 * decay;
+    (weighted_sum / PRICE_ONE) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SOL/USDC style market: 1 base unit is 1 SOL, ticks of 0.001 USDC, 1000 base lots per SOL
+    const MARKET: MarketParams = MarketParams {
+        quote_decimals: 6,
+        raw_base_units_per_base_unit: 1,
+        tick_size_in_quote_atoms_per_base_unit: 1_000,
+        base_lots_per_base_unit: 1_000,
+    };
+
+    #[test]
+    fn test_from_pyth_handles_both_exponent_signs() {
+        assert_eq!(
+            FixedPrice::from_pyth(2_012_345_678, -8)
+                .unwrap()
+                .to_string(),
+            "20.123456780000"
+        );
+        assert_eq!(
+            FixedPrice::from_pyth(3, 2).unwrap().to_string(),
+            "300.000000000000"
+        );
+        // Digits past the 12th decimal are dropped
+        assert_eq!(FixedPrice::from_pyth(19, -13).unwrap().raw(), 1);
+        assert_eq!(FixedPrice::from_pyth(1, -40).unwrap().raw(), 0);
+        assert_eq!(
+            FixedPrice::from_pyth(-1, -8),
+            Err(PriceError::NegativePrice)
+        );
+        assert_eq!(
+            FixedPrice::from_pyth(i64::MAX, 20),
+            Err(PriceError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_fair_price_in_ticks() {
+        let base_price = FixedPrice::from_pyth(2_012_345_678, -8).unwrap();
+        let quote_price = FixedPrice::from_pyth(100_000_000, -8).unwrap();
+        assert_eq!(
+            fair_price_in_ticks(base_price, quote_price, &MARKET),
+            Ok(20_123)
+        );
+        assert_eq!(
+            fair_price_in_ticks(base_price, FixedPrice::from_raw(0), &MARKET),
+            Err(PriceError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_bid_rounds_down_and_ask_rounds_up() {
+        // 3 bps of 20_123 ticks is 6.0369 ticks
+        assert_eq!(bid_price_in_ticks(20_123, 3), Ok(20_116));
+        assert_eq!(ask_price_in_ticks(20_123, 3), Ok(20_130));
+        assert_eq!(bid_price_in_ticks(20_000, 5), Ok(19_990));
+        assert_eq!(ask_price_in_ticks(20_000, 5), Ok(20_010));
+        assert_eq!(bid_price_in_ticks(0, 3), Ok(0));
+        assert_eq!(
+            bid_price_in_ticks(20_123, 10_000),
+            Err(PriceError::EdgeTooLarge)
+        );
+        assert_eq!(ask_price_in_ticks(u64::MAX, 1), Err(PriceError::Overflow));
+    }
+
+    #[test]
+    fn test_size_rounds_down_to_base_lots() {
+        // 100 USDC at 20.116 USDC per SOL is 4.97116... SOL
+        assert_eq!(size_in_base_lots(100_000_000, 20_116, &MARKET), Ok(4_971));
+        assert_eq!(
+            size_in_base_lots(100_000_000, 0, &MARKET),
+            Err(PriceError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_ln_one_plus() {
+        assert_eq!(ln_one_plus(0), Ok(0));
+        assert_eq!(ln_one_plus(PRICE_ONE), Ok(LN_2));
+        // ln(e) = 1
+        let ln_e = ln_one_plus(1_718_281_828_459).unwrap();
+        assert!(ln_e.abs_diff(PRICE_ONE) < 10, "{}", ln_e);
+        // ln(101) = 4.61512051684...
+        let ln_101 = ln_one_plus(100 * PRICE_ONE).unwrap();
+        assert!(ln_101.abs_diff(4_615_120_516_841) < 100, "{}", ln_101);
+    }
+
+    // γ = k = 0.1 per bps, so ln(1 + γ / k) / γ = 6.93 bps
+    const AS_PARAMS: AvellanedaStoikovParams = AvellanedaStoikovParams {
+        risk_aversion_ppm: 100_000,
+        order_arrival_intensity_ppm: 100_000,
+        horizon_in_seconds: 10,
+        variance_in_micro_bps_squared_per_second: 0,
+    };
+
+    #[test]
+    fn test_avellaneda_stoikov_without_inventory_is_symmetric() {
+        assert_eq!(
+            avellaneda_stoikov_prices_in_ticks(20_000, &AS_PARAMS, 0, 100, 0),
+            Ok((19_986, 20_014))
+        );
+        // The minimum half spread wins over the model
+        assert_eq!(
+            avellaneda_stoikov_prices_in_ticks(20_000, &AS_PARAMS, 0, 100, 20),
+            Ok((19_960, 20_040))
+        );
+        let no_intensity = AvellanedaStoikovParams {
+            order_arrival_intensity_ppm: 0,
+            ..AS_PARAMS
+        };
+        assert_eq!(
+            avellaneda_stoikov_prices_in_ticks(20_000, &no_intensity, 0, 100, 0),
+            Err(PriceError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_avellaneda_stoikov_skews_against_inventory() {
+        // σ² = 4 bps² per second gives γ·σ²·T = 4 bps and a half spread of 8.93 bps
+        let params = AvellanedaStoikovParams {
+            variance_in_micro_bps_squared_per_second: 4_000_000,
+            ..AS_PARAMS
+        };
+        assert_eq!(
+            avellaneda_stoikov_prices_in_ticks(20_000, &params, 0, 100, 0),
+            Ok((19_982, 20_018))
+        );
+        // Long two units moves the reservation price 8 bps down
+        assert_eq!(
+            avellaneda_stoikov_prices_in_ticks(20_000, &params, 200, 100, 0),
+            Ok((19_966, 20_002))
+        );
+        assert_eq!(
+            avellaneda_stoikov_prices_in_ticks(20_000, &params, -200, 100, 0),
+            Ok((19_998, 20_034))
+        );
+        // Long 20 units asks 71 bps below the fair price
+        assert_eq!(
+            avellaneda_stoikov_prices_in_ticks(20_000, &params, 2_000, 100, 0),
+            Ok((19_822, 19_858))
+        );
+        // Without a unit size the inventory is ignored
+        assert_eq!(
+            avellaneda_stoikov_prices_in_ticks(20_000, &params, 200, 0, 0),
+            Ok((19_982, 20_018))
+        );
+    }
+
+    #[test]
+    fn test_half_life_decay() {
+        assert_eq!(half_life_decay(0, 10), Ok(PRICE_ONE));
+        assert_eq!(half_life_decay(10, 10), Ok(PRICE_ONE / 2));
+        assert_eq!(half_life_decay(30, 10), Ok(PRICE_ONE / 8));
+        assert_eq!(half_life_decay(1, 0), Ok(0));
+        assert_eq!(half_life_decay(u64::MAX, 1), Ok(0));
+        // 2^(-1/2) = 0.70710678118654...
+        let decay = half_life_decay(5, 10).unwrap();
+        assert!(decay.abs_diff(707_106_781_186) < 10, "{}", decay);
+    }
+
+    #[test]
+    fn test_update_variance_ewma() {
+        // A 5 bps move in one second is a sample of 25 bps² per second
+        assert_eq!(update_variance_ewma(0, 20_000, 20_010, 1, 0), 25_000_000);
+        assert_eq!(update_variance_ewma(0, 20_000, 19_990, 1, 1), 12_500_000);
+        // One half-life halves the weight of the previous estimate
+        assert_eq!(
+            update_variance_ewma(1_000_000, 20_000, 20_010, 5, 5),
+            3_000_000
+        );
+        assert_eq!(update_variance_ewma(0, 20_000, 20_010, 5, 10), 1_464_466);
+        assert_eq!(update_variance_ewma(1_000_000, 0, 20_010, 5, 5), 1_000_000);
+        assert_eq!(
+            update_variance_ewma(1_000_000, 20_000, 20_010, 0, 5),
+            1_000_000
+        );
+    }
+
+    #[test]
+    fn test_update_variance_ewma_extreme_return() {
+        // A jump from one tick to u64::MAX saturates instead of failing
+        assert_eq!(update_variance_ewma(0, 1, u64::MAX, 1, 0), u64::MAX);
+        assert_eq!(
+            update_variance_ewma(1_000_000, 1, u64::MAX, u64::MAX, 1),
+            u64::MAX
+        );
+        // A crash to one tick is a -100% return
+        assert_eq!(
+            update_variance_ewma(0, u64::MAX, 1, 1, 0),
+            99_999_980_000_001
+        );
+        // Large enough to overflow the scaled product but not the sample itself
+        assert_eq!(
+            update_variance_ewma(0, 1 << 40, 1 << 42, 1 << 20, 0),
+            900_000_000_000_000 >> 20
+        );
+    }
+}
@@ -1,9 +1,100 @@
 use anchor_lang::prelude::*;
 
+use crate::constant::{MAX_SCHEDULE_PROFILES, MINUTES_PER_DAY};
+use crate::errors::StrategyError;
+use crate::instructions::{FairPriceMode, PriceImprovementBehavior, QuoteModel, SelfTradeBehavior};
+use crate::price::update_variance_ewma;
+
+/// Overrides the quote edge and size during a UTC time-of-day window on selected days.
+///
+/// Profiles have no number of levels: the strategy quotes and tracks a single order per side,
+/// so a per-profile level count would have nothing to apply to.
+#[zero_copy]
+#[derive(Default)]
+pub struct ScheduleProfile {
+    /// Bit `n` enables the windows starting on day `n` of the week, 0 is Sunday. An empty mask is
+    /// an unused slot.
+    pub day_mask: u8,
+    pub _padding0: u8,
+    /// Start of the window in minutes after midnight UTC, inclusive
+    pub start_minute_of_day: u16,
+    /// End of the window in minutes after midnight UTC, exclusive. Windows ending before they
+    /// start wrap past midnight and equal bounds cover the whole day.
+    pub end_minute_of_day: u16,
+    pub _padding1: [u8; 2],
+    /// Replaces the strategy edge while active, 0 keeps it
+    pub quote_edge_in_bps: u64,
+    /// Replaces the strategy size while active, 0 keeps it
+    pub quote_size_in_quote_atoms: u64,
+}
+
+// Every byte is a field, so the zero-copy layout has no uninitialized padding
+const _: () = assert!(std::mem::size_of::<ScheduleProfile>() == 24);
+
+impl ScheduleProfile {
+    pub fn is_active(&self, unix_timestamp: i64) -> bool {
+        let days_since_epoch = unix_timestamp.div_euclid(86_400);
+        // 1970-01-01 was a Thursday
+        let day_of_week = (days_since_epoch + 4).rem_euclid(7);
+        let minute_of_day = (unix_timestamp.rem_euclid(86_400) / 60) as u16;
+
+        let is_enabled_on = |day: i64| self.day_mask & (1 << day) != 0;
+        let (start, end) = (self.start_minute_of_day, self.end_minute_of_day);
+        match start.cmp(&end) {
+            std::cmp::Ordering::Less => {
+                is_enabled_on(day_of_week) && start <= minute_of_day && minute_of_day < end
+            }
+            // The part after midnight belongs to the window that started the day before
+            std::cmp::Ordering::Greater => {
+                (is_enabled_on(day_of_week) && start <= minute_of_day)
+                    || (is_enabled_on((day_of_week + 6) % 7) && minute_of_day < end)
+            }
+            std::cmp::Ordering::Equal => is_enabled_on(day_of_week),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.day_mask < 1 << 7
+            && self.start_minute_of_day < MINUTES_PER_DAY
+            && self.end_minute_of_day < MINUTES_PER_DAY
+            && self.quote_edge_in_bps < 10_000
+    }
+}
+
+/// Strategy layout before the admin, trader and guardian roles were split, upgraded by `migrate`.
+/// Its accounts carry the `PhoenixStrategyState` discriminator and the fields have no padding,
+/// so they deserialize as they were stored.
+#[derive(Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyPhoenixStrategyState {
+    pub trader: Pubkey,
+    pub market: Pubkey,
+    pub bid_order_sequence_number: u64,
+    pub bid_price_in_ticks: u64,
+    pub initial_bid_size_in_base_lots: u64,
+    pub ask_order_sequence_number: u64,
+    pub ask_price_in_ticks: u64,
+    pub initial_ask_size_in_base_lots: u64,
+    pub last_update_slot: u64,
+    pub last_update_unix_timestamp: i64,
+    pub quote_edge_in_bps: u64,
+    pub quote_size_in_quote_atoms: u64,
+    pub post_only: bool,
+    pub price_improvement_behavior: u8,
+    pub padding: [u8; 6],
+}
+
+impl LegacyPhoenixStrategyState {
+    pub const LEN: usize = 152;
+}
+
 #[account(zero_copy)]
 pub struct PhoenixStrategyState {
     pub trader: Pubkey,
     pub market: Pubkey,
+    // Roles, the admin manages the other roles and the guardian can only halt the strategy
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub guardian: Pubkey,
     // Order parameters
     pub bid_order_sequence_number: u64,
     pub bid_price_in_ticks: u64,
@@ -13,10 +104,320 @@ pub struct PhoenixStrategyState {
     pub initial_ask_size_in_base_lots: u64,
     pub last_update_slot: u64,
     pub last_update_unix_timestamp: i64,
+    pub min_slots_between_updates: u64,
     // Strategy parameters
     pub quote_edge_in_bps: u64,
     pub quote_size_in_quote_atoms: u64,
+    pub taker_fee_edge_multiplier_in_bps: u64,
+    pub hedge_cost_in_bps: u64,
     pub post_only: bool,
     pub price_improvement_behavior: u8,
-    pub padding: [u8; 6],
+    pub halted: bool,
+    pub last_fill_side: u8,
+    pub fair_price_mode: u8,
+    pub reject_post_only: bool,
+    pub self_trade_behavior: u8,
+    pub cancel_untracked_orders: bool,
+    pub quote_model: u8,
+    pub padding: [u8; 7],
+    pub ema_weight_in_bps: u64,
+    pub penny_jump_max_ticks: u64,
+    pub penny_jump_min_edge_in_bps: u64,
+    pub microprice_levels: u64,
+    pub microprice_weight_in_bps: u64,
+    pub risk_aversion_ppm: u64,
+    pub order_arrival_intensity_ppm: u64,
+    pub horizon_in_seconds: u64,
+    // Volatility estimate, an EWMA of the variance of oracle returns between updates
+    pub volatility_half_life_in_seconds: u64,
+    pub return_variance_in_micro_bps_squared_per_second: u64,
+    pub last_oracle_price_in_ticks: u64,
+    pub last_oracle_unix_timestamp: i64,
+    // Circuit breaker limits set by the admin with `set_circuit_breaker`, 0 disables a check
+    pub max_base_lots_filled_per_window: u64,
+    pub fill_window_in_slots: u64,
+    pub max_loss_in_quote_atoms: u64,
+    pub max_consecutive_one_sided_fills: u64,
+    // Circuit breaker state, cleared by `reset_circuit_breaker`
+    pub fill_window_start_slot: u64,
+    pub base_lots_filled_in_window: u64,
+    pub base_lots_position: i64,
+    pub quote_atoms_position: i64,
+    pub consecutive_one_sided_fills: u64,
+    // Schedule, the first active profile overrides the quote edge and size
+    pub schedule_profiles: [ScheduleProfile; MAX_SCHEDULE_PROFILES],
+}
+
+pub const NO_FILL_SIDE: u8 = 0;
+pub const BID_FILL_SIDE: u8 = 1;
+pub const ASK_FILL_SIDE: u8 = 2;
+
+impl PhoenixStrategyState {
+    pub fn get_price_improvement_behavior(&self) -> Result<PriceImprovementBehavior> {
+        Ok(PriceImprovementBehavior::from_u8(
+            self.price_improvement_behavior,
+            self.penny_jump_max_ticks,
+            self.penny_jump_min_edge_in_bps,
+        )
+        .ok_or(StrategyError::InvalidStrategyParams)?)
+    }
+
+    pub fn get_quote_model(&self) -> Result<QuoteModel> {
+        Ok(QuoteModel::from_u8(
+            self.quote_model,
+            self.risk_aversion_ppm,
+            self.order_arrival_intensity_ppm,
+            self.horizon_in_seconds,
+        )
+        .ok_or(StrategyError::InvalidStrategyParams)?)
+    }
+
+    pub fn get_fair_price_mode(&self) -> Result<FairPriceMode> {
+        Ok(
+            FairPriceMode::from_u8(self.fair_price_mode, self.ema_weight_in_bps)
+                .ok_or(StrategyError::InvalidStrategyParams)?,
+        )
+    }
+
+    pub fn get_self_trade_behavior(&self) -> Result<SelfTradeBehavior> {
+        Ok(SelfTradeBehavior::from_u8(self.self_trade_behavior)
+            .ok_or(StrategyError::InvalidStrategyParams)?)
+    }
+
+    /// Returns the quote edge and size of the first schedule profile active at `unix_timestamp`,
+    /// falling back to the strategy parameters.
+    pub fn get_scheduled_quote_params(&self, unix_timestamp: i64) -> (u64, u64) {
+        let mut quote_edge_in_bps = self.quote_edge_in_bps;
+        let mut quote_size_in_quote_atoms = self.quote_size_in_quote_atoms;
+        let schedule_profiles = self.schedule_profiles;
+        if let Some((index, profile)) = schedule_profiles
+            .iter()
+            .enumerate()
+            .find(|(_, profile)| profile.is_active(unix_timestamp))
+        {
+            msg!("Schedule profile {} is active", index);
+            if profile.quote_edge_in_bps > 0 {
+                quote_edge_in_bps = profile.quote_edge_in_bps;
+            }
+            if profile.quote_size_in_quote_atoms > 0 {
+                quote_size_in_quote_atoms = profile.quote_size_in_quote_atoms;
+            }
+        }
+        (quote_edge_in_bps, quote_size_in_quote_atoms)
+    }
+
+    /// Folds the return of the spot oracle price since the last update into the volatility
+    /// estimate. Updates within the same second keep the previous price so the next return spans
+    /// a non-zero interval. Returns the elapsed seconds when the estimate was updated.
+    pub fn update_volatility(
+        &mut self,
+        spot_price_in_ticks: u64,
+        unix_timestamp: i64,
+    ) -> Option<u64> {
+        let elapsed_seconds = unix_timestamp
+            .saturating_sub(self.last_oracle_unix_timestamp)
+            .max(0) as u64;
+        if elapsed_seconds == 0 && self.last_oracle_price_in_ticks != 0 {
+            return None;
+        }
+        self.return_variance_in_micro_bps_squared_per_second = update_variance_ewma(
+            self.return_variance_in_micro_bps_squared_per_second,
+            self.last_oracle_price_in_ticks,
+            spot_price_in_ticks,
+            elapsed_seconds,
+            self.volatility_half_life_in_seconds,
+        );
+        self.last_oracle_price_in_ticks = spot_price_in_ticks;
+        self.last_oracle_unix_timestamp = unix_timestamp;
+        Some(elapsed_seconds)
+    }
+
+    pub fn is_guardian_or_admin(&self, key: &Pubkey) -> bool {
+        *key == self.admin || (*key == self.guardian && self.guardian != Pubkey::default())
+    }
+
+    /// Adds the fills detected since the last update to the circuit breaker counters.
+    pub fn record_fills(
+        &mut self,
+        slot: u64,
+        bid_base_lots_filled: u64,
+        bid_quote_atoms_filled: u64,
+        ask_base_lots_filled: u64,
+        ask_quote_atoms_filled: u64,
+    ) {
+        if slot
+            >= self
+                .fill_window_start_slot
+                .saturating_add(self.fill_window_in_slots)
+        {
+            self.fill_window_start_slot = slot;
+            self.base_lots_filled_in_window = 0;
+        }
+        self.base_lots_filled_in_window = self
+            .base_lots_filled_in_window
+            .saturating_add(bid_base_lots_filled)
+            .saturating_add(ask_base_lots_filled);
+
+        // Buying base spends quote and selling base receives quote
+        self.base_lots_position = self
+            .base_lots_position
+            .saturating_add(bid_base_lots_filled as i64)
+            .saturating_sub(ask_base_lots_filled as i64);
+        self.quote_atoms_position = self
+            .quote_atoms_position
+            .saturating_add(ask_quote_atoms_filled as i64)
+            .saturating_sub(bid_quote_atoms_filled as i64);
+
+        match (bid_base_lots_filled > 0, ask_base_lots_filled > 0) {
+            (false, false) => {}
+            // Fills on both sides break the streak
+            (true, true) => {
+                self.consecutive_one_sided_fills = 0;
+                self.last_fill_side = NO_FILL_SIDE;
+            }
+            (bid_filled, _) => {
+                let fill_side = if bid_filled {
+                    BID_FILL_SIDE
+                } else {
+                    ASK_FILL_SIDE
+                };
+                if fill_side == self.last_fill_side {
+                    self.consecutive_one_sided_fills =
+                        self.consecutive_one_sided_fills.saturating_add(1);
+                } else {
+                    self.consecutive_one_sided_fills = 1;
+                }
+                self.last_fill_side = fill_side;
+            }
+        }
+    }
+
+    /// Returns true if any of the configured circuit breaker limits is breached.
+    /// `position_value_in_quote_atoms` is the base position since the last reset marked at the oracle price.
+    pub fn is_circuit_breaker_tripped(&self, position_value_in_quote_atoms: i128) -> bool {
+        let max_base_lots_filled = self.max_base_lots_filled_per_window;
+        if max_base_lots_filled > 0 && self.base_lots_filled_in_window > max_base_lots_filled {
+            msg!(
+                "Circuit breaker: {} base lots filled in the current window",
+                self.base_lots_filled_in_window
+            );
+            return true;
+        }
+
+        let pnl_in_quote_atoms = self.quote_atoms_position as i128 + position_value_in_quote_atoms;
+        let max_loss = self.max_loss_in_quote_atoms;
+        if max_loss > 0 && pnl_in_quote_atoms < -(max_loss as i128) {
+            msg!(
+                "Circuit breaker: mark-to-oracle loss of {} quote atoms",
+                -pnl_in_quote_atoms
+            );
+            return true;
+        }
+
+        let max_one_sided_fills = self.max_consecutive_one_sided_fills;
+        if max_one_sided_fills > 0 && self.consecutive_one_sided_fills > max_one_sided_fills {
+            msg!(
+                "Circuit breaker: {} consecutive one-sided fills",
+                self.consecutive_one_sided_fills
+            );
+            return true;
+        }
+        false
+    }
+
+    pub fn reset_circuit_breaker(&mut self, slot: u64) {
+        self.halted = false;
+        self.last_fill_side = NO_FILL_SIDE;
+        self.fill_window_start_slot = slot;
+        self.base_lots_filled_in_window = 0;
+        self.base_lots_position = 0;
+        self.quote_atoms_position = 0;
+        self.consecutive_one_sided_fills = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-06 00:00:00 UTC, a Saturday
+    const SATURDAY_MIDNIGHT: i64 = 1_704_499_200;
+    const FRIDAY: u8 = 1 << 5;
+    const SATURDAY: u8 = 1 << 6;
+    const SUNDAY: u8 = 1;
+
+    #[test]
+    fn test_schedule_profile_windows() {
+        let us_open = ScheduleProfile {
+            day_mask: SATURDAY,
+            start_minute_of_day: 13 * 60 + 30,
+            end_minute_of_day: 15 * 60,
+            ..Default::default()
+        };
+        assert!(!us_open.is_active(SATURDAY_MIDNIGHT + (13 * 60 + 29) * 60));
+        assert!(us_open.is_active(SATURDAY_MIDNIGHT + (13 * 60 + 30) * 60));
+        assert!(!us_open.is_active(SATURDAY_MIDNIGHT + 15 * 60 * 60));
+        // Same time on Sunday
+        assert!(!us_open.is_active(SATURDAY_MIDNIGHT + 86_400 + 14 * 60 * 60));
+
+        let overnight = ScheduleProfile {
+            day_mask: SATURDAY | SUNDAY,
+            start_minute_of_day: 22 * 60,
+            end_minute_of_day: 2 * 60,
+            ..Default::default()
+        };
+        assert!(overnight.is_active(SATURDAY_MIDNIGHT + 23 * 60 * 60));
+        assert!(!overnight.is_active(SATURDAY_MIDNIGHT + 12 * 60 * 60));
+        // After midnight the window runs on if it started the day before
+        assert!(!overnight.is_active(SATURDAY_MIDNIGHT + 60 * 60));
+        assert!(overnight.is_active(SATURDAY_MIDNIGHT + 86_400 + 60 * 60));
+        assert!(overnight.is_active(SATURDAY_MIDNIGHT + 2 * 86_400 + 60 * 60));
+        assert!(!overnight.is_active(SATURDAY_MIDNIGHT + 2 * 86_400 + 23 * 60 * 60));
+
+        // Friday 22:00 to Saturday 02:00
+        let friday_night = ScheduleProfile {
+            day_mask: FRIDAY,
+            start_minute_of_day: 22 * 60,
+            end_minute_of_day: 2 * 60,
+            ..Default::default()
+        };
+        assert!(friday_night.is_active(SATURDAY_MIDNIGHT - 60 * 60));
+        assert!(friday_night.is_active(SATURDAY_MIDNIGHT + 60 * 60));
+        assert!(!friday_night.is_active(SATURDAY_MIDNIGHT + 22 * 60 * 60));
+        assert!(!friday_night.is_active(SATURDAY_MIDNIGHT - 86_400 + 60 * 60));
+
+        let weekend = ScheduleProfile {
+            day_mask: SATURDAY | SUNDAY,
+            ..Default::default()
+        };
+        assert!(weekend.is_active(SATURDAY_MIDNIGHT + 86_400 + 86_399));
+        assert!(!weekend.is_active(SATURDAY_MIDNIGHT + 2 * 86_400));
+        assert!(!ScheduleProfile::default().is_active(SATURDAY_MIDNIGHT));
+    }
+
+    #[test]
+    fn test_legacy_strategy_layout() {
+        let legacy = LegacyPhoenixStrategyState {
+            trader: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            bid_order_sequence_number: 1,
+            bid_price_in_ticks: 2,
+            initial_bid_size_in_base_lots: 3,
+            ask_order_sequence_number: 4,
+            ask_price_in_ticks: 5,
+            initial_ask_size_in_base_lots: 6,
+            last_update_slot: 7,
+            last_update_unix_timestamp: 8,
+            quote_edge_in_bps: 9,
+            quote_size_in_quote_atoms: 10,
+            post_only: true,
+            price_improvement_behavior: 2,
+            padding: [0; 6],
+        };
+        let bytes = legacy.try_to_vec().unwrap();
+        assert_eq!(bytes.len(), LegacyPhoenixStrategyState::LEN);
+        // Same offsets as the zero-copy account the legacy program stored
+        assert_eq!(bytes[64..72], 1u64.to_le_bytes());
+        assert_eq!(bytes[144..146], [1, 2]);
+    }
 }